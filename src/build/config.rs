@@ -1,4 +1,6 @@
+use crate::package::{GitSource, PackageSource};
 use crate::result::{OpenCliError, Result};
+use crate::utils::platform::Platform;
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 use std::collections::HashMap;
@@ -9,6 +11,9 @@ use tokio::fs;
 pub struct BuildConfig {
     pub build: Build,
     pub packages: Option<HashMap<SmolStr, PackageSpec>>,
+    /// Local checkouts used in place of a package while developing it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<HashMap<SmolStr, PackagePatch>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +23,7 @@ pub struct Build {
     pub compiler_version: String,
     pub includes: Option<BuildIncludes>,
     pub args: Option<BuildArgs>,
+    pub compiler_checksums: Option<HashMap<String, String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,13 +40,67 @@ pub struct BuildArgs {
 #[serde(untagged)]
 pub enum PackageSpec {
     Simple(SmolStr),
-    Detailed {
-        version: SmolStr,
-        target: Option<PackageTarget>,
-    },
+    Detailed(Box<PackageDetails>),
 }
 
+/// Table form of a package entry; at most one of `git`, `gitlab`, `gitea`, `url` or `path`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<SmolStr>,
+    pub target: Option<PackageTarget>,
+    /// Platforms whose assets are installed, this machine's when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platforms: Option<Vec<Platform>>,
+    /// Globs of the release assets to download, instead of every asset for `platforms`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assets: Option<Vec<SmolStr>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<PackageFileFilter>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gitlab: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gitea: Option<SmolStr>,
+    /// Server of a GitLab or Gitea project
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<SmolStr>,
+}
+
+/// `files` table of a package entry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageFileFilter {
+    /// Globs of downloaded files that are never installed, e.g. `*.pdb` or `examples/**`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<SmolStr>,
+}
+
+/** `[patch."owner/repo"]` entry
+ *
+ * ```toml
+ * [patch."pawn-lang/YSI-Includes"]
+ * path = "../YSI-Includes"
+ * ```
+ */
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackagePatch {
+    pub path: SmolStr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum PackageTarget {
     Components,
@@ -66,8 +126,10 @@ impl Default for BuildConfig {
                         "-Z+".to_string(),
                     ],
                 }),
+                compiler_checksums: None,
             },
             packages: None,
+            patch: None,
         }
     }
 }
@@ -128,6 +190,25 @@ impl BuildConfig {
         self.packages.as_ref()
     }
 
+    /// Patched packages and the local path replacing each of them
+    pub fn get_patches(&self) -> Result<Vec<(SmolStr, SmolStr)>> {
+        let mut patches = Vec::new();
+        for (name, patch) in self.patch.iter().flatten() {
+            PackageSource::Path {
+                path: patch.path.clone(),
+            }
+            .validate(&format!("{} in [patch]", name))?;
+            patches.push((name.clone(), patch.path.clone()));
+        }
+        patches.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(patches)
+    }
+
+    pub fn get_compiler_checksums(&self) -> HashMap<String, String> {
+        self.build.compiler_checksums.clone().unwrap_or_default()
+    }
+
     pub fn get_include_paths(&self) -> Vec<PathBuf> {
         self.build
             .includes
@@ -141,15 +222,116 @@ impl PackageSpec {
     pub fn version(&self) -> &str {
         match self {
             PackageSpec::Simple(version) => version,
-            PackageSpec::Detailed { version, .. } => version,
+            PackageSpec::Detailed(details) => details.version.as_deref().unwrap_or("*"),
         }
     }
 
     pub fn target(&self) -> Option<&PackageTarget> {
         match self {
             PackageSpec::Simple(_) => None,
-            PackageSpec::Detailed { target, .. } => target.as_ref(),
+            PackageSpec::Detailed(details) => details.target.as_ref(),
+        }
+    }
+
+    pub fn platforms(&self) -> Option<&[Platform]> {
+        match self {
+            PackageSpec::Simple(_) => None,
+            PackageSpec::Detailed(details) => details.platforms.as_deref(),
+        }
+    }
+
+    /// Asset globs from `assets`, empty to download every asset for the platforms
+    pub fn asset_patterns(&self) -> &[SmolStr] {
+        match self {
+            PackageSpec::Simple(_) => &[],
+            PackageSpec::Detailed(details) => details.assets.as_deref().unwrap_or_default(),
+        }
+    }
+
+    /// File globs from `files.exclude`
+    pub fn excluded_files(&self) -> &[SmolStr] {
+        match self {
+            PackageSpec::Simple(_) => &[],
+            PackageSpec::Detailed(details) => details
+                .files
+                .as_ref()
+                .map_or(&[], |files| files.exclude.as_slice()),
+        }
+    }
+
+    /** Where the package comes from, `None` for GitHub releases
+     *
+     * # Errors
+     * - `OpenCliError::Config` when the entry mixes sources or misses a required key
+     */
+    pub fn source(&self, name: &str) -> Result<Option<PackageSource>> {
+        let PackageSpec::Detailed(details) = self else {
+            return Ok(None);
+        };
+        let invalid = |message: &str| {
+            Err(OpenCliError::Config(
+                format!("Package {}: {}", name, message).into(),
+            ))
+        };
+
+        let kinds = [
+            details.git.is_some(),
+            details.gitlab.is_some(),
+            details.gitea.is_some(),
+            details.url.is_some(),
+            details.path.is_some(),
+        ];
+        if kinds.iter().filter(|kind| **kind).count() > 1 {
+            return invalid("use only one of git, gitlab, gitea, url or path");
+        }
+        if details.git.is_none()
+            && (details.rev.is_some() || details.branch.is_some() || details.tag.is_some())
+        {
+            return invalid("rev, branch and tag need git");
+        }
+        if details.host.is_some() && details.gitlab.is_none() && details.gitea.is_none() {
+            return invalid("host needs gitlab or gitea");
+        }
+        if details.sha256.is_some() && details.url.is_none() {
+            return invalid("sha256 needs url");
         }
+
+        let source = if let Some(git) = &details.git {
+            PackageSource::Git(GitSource {
+                git: git.clone(),
+                rev: details.rev.clone(),
+                branch: details.branch.clone(),
+                tag: details.tag.clone(),
+            })
+        } else if let Some(gitlab) = &details.gitlab {
+            PackageSource::GitLab {
+                gitlab: gitlab.clone(),
+                host: details.host.clone(),
+            }
+        } else if let Some(gitea) = &details.gitea {
+            let Some(host) = &details.host else {
+                return invalid("gitea needs host, e.g. \"https://gitea.example.com\"");
+            };
+            PackageSource::Gitea {
+                gitea: gitea.clone(),
+                host: host.clone(),
+            }
+        } else if let Some(url) = &details.url {
+            let Some(sha256) = &details.sha256 else {
+                return invalid("url needs sha256");
+            };
+            PackageSource::Url {
+                url: url.clone(),
+                sha256: sha256.to_lowercase().into(),
+            }
+        } else if let Some(path) = &details.path {
+            PackageSource::Path { path: path.clone() }
+        } else {
+            return Ok(None);
+        };
+
+        source.validate(name)?;
+        Ok(Some(source))
     }
 
     pub fn new_simple(version: impl Into<SmolStr>) -> Self {
@@ -157,9 +339,10 @@ impl PackageSpec {
     }
 
     pub fn new_detailed(version: impl Into<SmolStr>, target: Option<PackageTarget>) -> Self {
-        PackageSpec::Detailed {
-            version: version.into(),
+        PackageSpec::Detailed(Box::new(PackageDetails {
+            version: Some(version.into()),
             target,
-        }
+            ..Default::default()
+        }))
     }
 }
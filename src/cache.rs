@@ -1,9 +1,17 @@
+use crate::config::UserConfig;
+use crate::package::PackageDependency;
 use crate::result::{OpenCliError, Result};
+use crate::security::SecurityManager;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Numbers temporary files so concurrent writers never share one
+static TEMP_COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
 
 /** Manages a persistent cache of file hashes stored in a text file
  *
@@ -489,6 +497,574 @@ impl CacheManager {
     } // list_files_cache
 }
 
+/** Content-addressed cache of downloaded package assets shared by every project
+ *
+ * Layout under the OpenCLI config directory:
+ * ```text
+ * cache/packages/
+ *   index.toml              # (repo, tag, asset) -> sha256
+ *   blobs/ab/abcdef...      # asset contents named by their SHA-256
+ * ```
+ *
+ * Identical assets published by several releases or repositories are stored once.
+ * Blobs are re-hashed before use, so a corrupted entry is treated as a miss.
+ *
+ * # Example
+ * ```no_run
+ * use opencli::cache::PackageCache;
+ * use std::path::Path;
+ *
+ * #[tokio::main(flavor = "current_thread")]
+ * async fn main() -> Result<(), Box<dyn std::error::Error>> {
+ *     let cache = PackageCache::new()?;
+ *
+ *     let restored = cache
+ *         .restore("Y-Less/sscanf", "v2.13.8", "sscanf.zip", None, Path::new("sscanf.zip"))
+ *         .await?;
+ *
+ *     if restored.is_none() {
+ *         // download, then:
+ *         // cache.store("Y-Less/sscanf", "v2.13.8", "sscanf.zip", url, Path::new("sscanf.zip"), &sha256).await?;
+ *     }
+ *
+ *     Ok(())
+ * }
+ * ```
+ */
+pub struct PackageCache {
+    // Root of the package cache (index plus blobs)
+    base_dir: PathBuf,
+    // Serializes index rewrites between concurrent installs of this process; other
+    // processes are kept out by the file lock taken in `lock`
+    index_lock: Mutex<()>,
+}
+
+/// Held while the cache index or blobs are changed, released on drop
+struct PackageCacheLock<'a> {
+    _guard: MutexGuard<'a, ()>,
+    _file: std::fs::File,
+}
+
+/** One cached asset of a package release */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageCacheEntry {
+    pub repo: String,
+    pub tag: String,
+    pub asset: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub url: String,
+    pub sha256: String,
+    pub size: u64,
+    pub cached_at: String,
+}
+
+/** Outcome of re-hashing every cached blob */
+#[derive(Debug, Default)]
+pub struct CacheVerifyReport {
+    pub valid: usize,
+    pub corrupt: Vec<PackageCacheEntry>,
+    pub missing: Vec<PackageCacheEntry>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CachedManifest {
+    #[serde(default)]
+    dependencies: Vec<PackageDependency>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PackageCacheIndex {
+    #[serde(default)]
+    entries: Vec<PackageCacheEntry>,
+}
+
+impl PackageCache {
+    /** Opens the global package cache in the OpenCLI config directory
+     *
+     * # Errors
+     * - `OpenCliError::Config` if the platform config directory is unknown
+     */
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_base_dir(
+            UserConfig::base_directory()?.join("cache").join("packages"),
+        ))
+    }
+
+    /** Opens a package cache rooted at an arbitrary directory */
+    pub fn with_base_dir(base_dir: PathBuf) -> Self {
+        Self {
+            base_dir,
+            index_lock: Mutex::new(()),
+        }
+    }
+
+    /** Returns the cache root directory */
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /** Copies a cached asset to `destination` if the cache holds it
+     *
+     * # Arguments
+     * * `digest` - Digest published for the asset (e.g. `sha256:...`); when given the
+     *   blob is looked up by content, otherwise by `(repo, tag, asset)`
+     *
+     * # Returns
+     * - `Ok(Some(sha256))` if the asset was restored from cache
+     * - `Ok(None)` on a cache miss or when the cached blob failed verification
+     */
+    pub async fn restore(
+        &self,
+        repo: &str,
+        tag: &str,
+        asset: &str,
+        digest: Option<&str>,
+        destination: &Path,
+    ) -> Result<Option<String>> {
+        // Held until the blob is copied and recorded, so a clean in another process cannot drop it
+        let _lock = self.lock().await?;
+
+        let sha256 = match digest.and_then(Self::parse_sha256) {
+            Some(sha256) => sha256,
+            None => match self.lookup(repo, tag, asset).await? {
+                Some(entry) => entry.sha256,
+                None => return Ok(None),
+            },
+        };
+
+        let blob = self.blob_path(&sha256);
+        if !blob.exists() {
+            return Ok(None);
+        }
+
+        let actual = SecurityManager::new().sha256_file(&blob).await?;
+        if !SecurityManager::digest_matches(&sha256, &actual) {
+            log::warn!(
+                "Cached blob for {} {} {} is corrupt, discarding",
+                repo,
+                tag,
+                asset
+            );
+            fs::remove_file(&blob).await?;
+            return Ok(None);
+        }
+
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::copy(&blob, destination).await?;
+
+        // Record the key even for digest hits so `cache list` shows every release using the blob
+        self.record(repo, tag, asset, "", &sha256, actual_size(&blob).await?)
+            .await?;
+
+        log::info!("Restored {} {} {} from package cache", repo, tag, asset);
+        Ok(Some(sha256))
+    }
+
+    /** Adds a downloaded asset to the cache
+     *
+     * # Arguments
+     * * `url` - Where the asset was downloaded from, kept for offline resolution
+     * * `file` - Downloaded asset to copy into the blob store
+     * * `sha256` - Hex SHA-256 of `file`
+     */
+    pub async fn store(
+        &self,
+        repo: &str,
+        tag: &str,
+        asset: &str,
+        url: &str,
+        file: &Path,
+        sha256: &str,
+    ) -> Result<()> {
+        let sha256 = sha256.to_lowercase();
+        let blob = self.blob_path(&sha256);
+        let _lock = self.lock().await?;
+
+        if !blob.exists() {
+            if let Some(parent) = blob.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+
+            // Copy then rename so a crash never leaves a truncated blob behind
+            let temp = temp_path(&blob);
+            fs::copy(file, &temp).await?;
+            fs::rename(&temp, &blob).await?;
+        }
+
+        self.record(repo, tag, asset, url, &sha256, actual_size(&blob).await?)
+            .await
+    }
+
+    /** Lists every cached asset, sorted by repository, tag and asset name */
+    pub async fn list(&self) -> Result<Vec<PackageCacheEntry>> {
+        let mut entries = self.load_index().await?.entries;
+        entries.sort_by(|a, b| (&a.repo, &a.tag, &a.asset).cmp(&(&b.repo, &b.tag, &b.asset)));
+        Ok(entries)
+    }
+
+    /** Groups the cached assets of a repository by release tag
+     *
+     * # Returns
+     * `(tag, entries)` pairs; used to resolve versions without the GitHub API in offline mode
+     */
+    pub async fn releases(&self, repo: &str) -> Result<Vec<(String, Vec<PackageCacheEntry>)>> {
+        let mut releases: Vec<(String, Vec<PackageCacheEntry>)> = Vec::new();
+
+        for entry in self.list().await? {
+            if !entry.repo.eq_ignore_ascii_case(repo) {
+                continue;
+            }
+
+            match releases.iter_mut().find(|(tag, _)| *tag == entry.tag) {
+                Some((_, entries)) => entries.push(entry),
+                None => releases.push((entry.tag.clone(), vec![entry])),
+            }
+        }
+
+        Ok(releases)
+    }
+
+    /** Returns the number of blobs and their total size in bytes */
+    pub async fn size(&self) -> Result<(usize, u64)> {
+        let mut count = 0;
+        let mut total = 0;
+
+        for blob in self.blob_files().await? {
+            count += 1;
+            total += actual_size(&blob).await?;
+        }
+
+        Ok((count, total))
+    }
+
+    /** Removes cached assets
+     *
+     * # Arguments
+     * * `repo` - Only remove entries of this repository; `None` empties the cache
+     *
+     * # Returns
+     * Number of blobs removed and the bytes freed
+     */
+    pub async fn clean(&self, repo: Option<&str>) -> Result<(usize, u64)> {
+        let _lock = self.lock().await?;
+
+        let mut index = self.load_index().await?;
+        if let Some(repo) = repo {
+            index.entries.retain(|e| !e.repo.eq_ignore_ascii_case(repo));
+        } else {
+            index.entries.clear();
+        }
+
+        let (removed, freed) = self.remove_unreferenced(&index).await?;
+        self.save_index(&index).await?;
+
+        let manifests = match repo {
+            Some(repo) => self.manifest_dir(repo),
+            None => self.base_dir.join("manifests"),
+        };
+        if manifests.exists() {
+            fs::remove_dir_all(&manifests).await?;
+        }
+
+        Ok((removed, freed))
+    }
+
+    /** Returns the dependencies recorded for a release, if they were cached
+     *
+     * # Returns
+     * - `Ok(None)` if the release's manifest was never fetched
+     */
+    pub async fn dependencies(
+        &self,
+        repo: &str,
+        tag: &str,
+    ) -> Result<Option<Vec<PackageDependency>>> {
+        let path = self.manifest_path(repo, tag);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path).await?;
+        match toml::from_str::<CachedManifest>(&content) {
+            Ok(manifest) => Ok(Some(manifest.dependencies)),
+            Err(e) => {
+                log::warn!("Cached manifest of {} {} is corrupt: {}", repo, tag, e);
+                Ok(None)
+            }
+        }
+    }
+
+    /** Records the dependencies declared by a release so offline resolution can reuse them */
+    pub async fn store_dependencies(
+        &self,
+        repo: &str,
+        tag: &str,
+        dependencies: &[PackageDependency],
+    ) -> Result<()> {
+        let path = self.manifest_path(repo, tag);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let manifest = CachedManifest {
+            dependencies: dependencies.to_vec(),
+        };
+        let temp = temp_path(&path);
+        fs::write(&temp, toml::to_string_pretty(&manifest)?).await?;
+        fs::rename(&temp, &path).await?;
+
+        Ok(())
+    }
+
+    /** Re-hashes every cached blob, dropping corrupt and missing entries
+     *
+     * # Returns
+     * Report listing valid, corrupt and missing entries
+     */
+    pub async fn verify(&self) -> Result<CacheVerifyReport> {
+        let _lock = self.lock().await?;
+
+        let security = SecurityManager::new();
+        let mut index = self.load_index().await?;
+        let mut report = CacheVerifyReport::default();
+        let mut checked: HashMap<String, bool> = HashMap::new();
+
+        for entry in &index.entries {
+            let blob = self.blob_path(&entry.sha256);
+
+            let valid = match checked.get(&entry.sha256) {
+                Some(valid) => *valid,
+                None if !blob.exists() => {
+                    report.missing.push(entry.clone());
+                    continue;
+                }
+                None => {
+                    let actual = security.sha256_file(&blob).await?;
+                    let valid = SecurityManager::digest_matches(&entry.sha256, &actual);
+                    checked.insert(entry.sha256.clone(), valid);
+                    valid
+                }
+            };
+
+            if valid {
+                report.valid += 1;
+            } else {
+                report.corrupt.push(entry.clone());
+            }
+        }
+
+        for (sha256, valid) in &checked {
+            if !valid {
+                fs::remove_file(self.blob_path(sha256)).await?;
+            }
+        }
+
+        index
+            .entries
+            .retain(|e| checked.get(&e.sha256).copied().unwrap_or(false));
+        self.remove_unreferenced(&index).await?;
+        self.save_index(&index).await?;
+
+        Ok(report)
+    }
+
+    async fn lookup(
+        &self,
+        repo: &str,
+        tag: &str,
+        asset: &str,
+    ) -> Result<Option<PackageCacheEntry>> {
+        Ok(self
+            .load_index()
+            .await?
+            .entries
+            .into_iter()
+            .find(|e| e.repo.eq_ignore_ascii_case(repo) && e.tag == tag && e.asset == asset))
+    }
+
+    /// Adds or replaces an index entry; the caller holds `lock`
+    async fn record(
+        &self,
+        repo: &str,
+        tag: &str,
+        asset: &str,
+        url: &str,
+        sha256: &str,
+        size: u64,
+    ) -> Result<()> {
+        let mut index = self.load_index().await?;
+        let is_key = |e: &PackageCacheEntry| {
+            e.repo.eq_ignore_ascii_case(repo) && e.tag == tag && e.asset == asset
+        };
+
+        // Keep the known download URL when a cache hit re-records the entry
+        let url = match index.entries.iter().find(|e| is_key(e)) {
+            Some(existing) if url.is_empty() => existing.url.clone(),
+            _ => url.to_string(),
+        };
+
+        index.entries.retain(|e| !is_key(e));
+        index.entries.push(PackageCacheEntry {
+            repo: repo.to_string(),
+            tag: tag.to_string(),
+            asset: asset.to_string(),
+            url,
+            sha256: sha256.to_string(),
+            size,
+            cached_at: chrono::Utc::now().to_rfc3339(),
+        });
+
+        self.save_index(&index).await
+    }
+
+    async fn remove_unreferenced(&self, index: &PackageCacheIndex) -> Result<(usize, u64)> {
+        let mut removed = 0;
+        let mut freed = 0;
+
+        for blob in self.blob_files().await? {
+            let name = blob
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+
+            if !index.entries.iter().any(|e| e.sha256 == name) {
+                freed += actual_size(&blob).await?;
+                fs::remove_file(&blob).await?;
+                removed += 1;
+            }
+        }
+
+        Ok((removed, freed))
+    }
+
+    async fn blob_files(&self) -> Result<Vec<PathBuf>> {
+        let blobs_dir = self.base_dir.join("blobs");
+        let mut files = Vec::new();
+
+        if !blobs_dir.exists() {
+            return Ok(files);
+        }
+
+        let mut shards = fs::read_dir(&blobs_dir).await?;
+        while let Some(shard) = shards.next_entry().await? {
+            if !shard.file_type().await?.is_dir() {
+                continue;
+            }
+
+            let mut blobs = fs::read_dir(shard.path()).await?;
+            while let Some(blob) = blobs.next_entry().await? {
+                let path = blob.path();
+                if blob.file_type().await?.is_file() && path.extension().is_none() {
+                    files.push(path);
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    async fn load_index(&self) -> Result<PackageCacheIndex> {
+        let path = self.base_dir.join("index.toml");
+
+        if !path.exists() {
+            return Ok(PackageCacheIndex::default());
+        }
+
+        let content = fs::read_to_string(&path).await?;
+        match toml::from_str(&content) {
+            Ok(index) => Ok(index),
+            Err(e) => {
+                log::warn!("Package cache index is corrupt, starting fresh: {}", e);
+                Ok(PackageCacheIndex::default())
+            }
+        }
+    }
+
+    async fn save_index(&self, index: &PackageCacheIndex) -> Result<()> {
+        fs::create_dir_all(&self.base_dir).await?;
+
+        let path = self.base_dir.join("index.toml");
+        let temp = temp_path(&path);
+        fs::write(&temp, toml::to_string_pretty(index)?).await?;
+        fs::rename(&temp, &path).await?;
+
+        Ok(())
+    }
+
+    /** Locks the cache against this and every other opencli process
+     *
+     * Blocks until a concurrent install, clean or verify elsewhere has finished.
+     */
+    async fn lock(&self) -> Result<PackageCacheLock<'_>> {
+        let guard = self.index_lock.lock().await;
+
+        fs::create_dir_all(&self.base_dir).await?;
+        let path = self.base_dir.join(".lock");
+        let file = tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)?;
+            file.lock()?;
+            Ok::<_, std::io::Error>(file)
+        })
+        .await
+        .map_err(|e| {
+            OpenCliError::Process(format!("Failed to lock the package cache: {}", e).into())
+        })??;
+
+        Ok(PackageCacheLock {
+            _guard: guard,
+            _file: file,
+        })
+    }
+
+    fn blob_path(&self, sha256: &str) -> PathBuf {
+        let sha256 = sha256.to_lowercase();
+        let shard = sha256.get(..2).unwrap_or("00");
+        self.base_dir.join("blobs").join(shard).join(&sha256)
+    }
+
+    fn manifest_dir(&self, repo: &str) -> PathBuf {
+        self.base_dir
+            .join("manifests")
+            .join(repo.to_lowercase().replace('/', "__"))
+    }
+
+    fn manifest_path(&self, repo: &str, tag: &str) -> PathBuf {
+        self.manifest_dir(repo)
+            .join(format!("{}.toml", tag.replace(['/', '\\'], "_")))
+    }
+
+    fn parse_sha256(digest: &str) -> Option<String> {
+        let hex = digest.trim().strip_prefix("sha256:")?;
+
+        if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            Some(hex.to_lowercase())
+        } else {
+            None
+        }
+    }
+}
+
+/// `<path>.<pid>-<n>.tmp`, unique to this writer
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    PathBuf::from(name)
+}
+
+async fn actual_size(path: &Path) -> Result<u64> {
+    Ok(fs::metadata(path).await?.len())
+}
+
 /*
  * Performance Considerations:
  *
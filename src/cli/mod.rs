@@ -14,6 +14,13 @@ use clap::Parser;
     help_template = "{before-help}{name} v{version}\nAuthor: {author}\n\n{about-with-newline}\n{usage-heading} {usage}\n\n{all-args}{after-help}"
 )]
 pub struct Cli {
+    #[arg(
+        long,
+        global = true,
+        help = "Never touch the network; serve from local caches or fail (also OPENCLI_OFFLINE=1)"
+    )]
+    offline: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -53,11 +60,23 @@ pub enum Commands {
         component: InstallComponent,
     },
 
+    #[command(about = "Compiler management commands")]
+    Compiler {
+        #[command(subcommand)]
+        action: CompilerAction,
+    },
+
     #[command(about = "Package management commands")]
     Package {
         #[command(subcommand)]
         action: PackageAction,
     },
+
+    #[command(about = "Global package cache commands")]
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
 }
 
 #[derive(Parser)]
@@ -72,6 +91,18 @@ pub enum InstallComponent {
     },
 }
 
+#[derive(Parser)]
+pub enum CompilerAction {
+    #[command(about = "List installed compilers")]
+    List {
+        #[arg(long, help = "List releases available from the compiler sources")]
+        remote: bool,
+    },
+
+    #[command(about = "Re-resolve the compiler version constraint and update the lock")]
+    Update,
+}
+
 #[derive(Parser)]
 pub enum PackageAction {
     #[command(about = "Install packages")]
@@ -81,6 +112,51 @@ pub enum PackageAction {
 
         #[arg(long, help = "Target folder (components or plugins)")]
         target: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 4,
+            help = "Maximum number of packages to download in parallel"
+        )]
+        jobs: usize,
+
+        #[arg(long, help = "Install exactly what opencli.lock records")]
+        locked: bool,
+
+        #[arg(
+            long,
+            help = "Like --locked, but fail if opencli.toml and opencli.lock disagree"
+        )]
+        frozen: bool,
+
+        #[arg(
+            long,
+            value_name = "PLATFORM",
+            help = "Install assets for these platforms instead of this one (windows, linux, macos; comma separated)"
+        )]
+        platform: Option<String>,
+    },
+
+    #[command(about = "Install, upgrade, downgrade or remove packages to match opencli.toml")]
+    Sync {
+        #[arg(long, help = "Print the plan without changing anything")]
+        dry_run: bool,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 4,
+            help = "Maximum number of packages to download in parallel"
+        )]
+        jobs: usize,
+
+        #[arg(
+            long,
+            value_name = "PLATFORM",
+            help = "Install assets for these platforms instead of this one (windows, linux, macos; comma separated)"
+        )]
+        platform: Option<String>,
     },
 
     #[command(about = "Remove package")]
@@ -95,6 +171,21 @@ pub enum PackageAction {
     #[command(about = "Check package integrity")]
     Check,
 
+    #[command(about = "Show the dependency tree of installed packages")]
+    Tree,
+
+    #[command(about = "Explain why a package or file is installed")]
+    Why {
+        #[arg(help = "Package (owner/repo) or installed file, e.g. sscanf2.inc")]
+        target: String,
+    },
+
+    #[command(about = "Show installed, allowed and newest versions of packages")]
+    Outdated {
+        #[arg(long, help = "Print the report as JSON")]
+        json: bool,
+    },
+
     #[command(about = "Update package")]
     Update {
         #[arg(help = "Package to update (owner/repo)")]
@@ -102,7 +193,46 @@ pub enum PackageAction {
 
         #[arg(long, help = "Update all packages")]
         all: bool,
+
+        #[arg(
+            long,
+            help = "Print the plan and release notes without changing anything"
+        )]
+        dry_run: bool,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 4,
+            help = "Maximum number of packages to download in parallel"
+        )]
+        jobs: usize,
+
+        #[arg(
+            long,
+            value_name = "PLATFORM",
+            help = "Install assets for these platforms instead of this one (windows, linux, macos; comma separated)"
+        )]
+        platform: Option<String>,
+    },
+}
+
+#[derive(Parser)]
+pub enum CacheAction {
+    #[command(about = "List cached package assets")]
+    List,
+
+    #[command(about = "Show package cache disk usage")]
+    Size,
+
+    #[command(about = "Remove cached package assets")]
+    Clean {
+        #[arg(help = "Only remove assets of this package (owner/repo)")]
+        package: Option<String>,
     },
+
+    #[command(about = "Re-hash cached assets and drop corrupt ones")]
+    Verify,
 }
 
 impl Default for Cli {
@@ -117,6 +247,10 @@ impl Cli {
     }
 
     pub async fn execute(self) -> Result<()> {
+        if self.offline {
+            crate::utils::download::set_offline(true);
+        }
+
         let mut executor = CommandExecutor::new();
 
         match self.command {
@@ -137,7 +271,9 @@ impl Cli {
                     executor.install_compiler(version, force).await
                 }
             },
+            Commands::Compiler { action } => executor.handle_compiler_action(action).await,
             Commands::Package { action } => executor.handle_package_action(action).await,
+            Commands::Cache { action } => executor.handle_cache_action(action).await,
         }
     }
 }
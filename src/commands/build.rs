@@ -1,5 +1,6 @@
 use crate::build::BuildConfig;
 use crate::compiler::CompilerManager;
+use crate::package::PackageManager;
 use crate::result::{OpenCliError, Result};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::path::Path;
@@ -49,7 +50,19 @@ impl BuildCommand {
         build_spinner.set_message("Loading build configuration...");
         build_spinner.enable_steady_tick(std::time::Duration::from_millis(100));
 
-        let config = self.load_build_config(config_path).await?;
+        let config_file = config_path.unwrap_or_else(|| "opencli.toml".to_string());
+        let config = self.load_build_config(&config_file).await?;
+        let lock_path = Path::new(&config_file).with_extension("lock");
+
+        if config.patch.as_ref().is_some_and(|patch| !patch.is_empty()) {
+            build_spinner.finish_and_clear();
+            let workspace_root = std::env::current_dir()?;
+            let config_path = workspace_root.join(&config_file);
+            PackageManager::new(workspace_root.as_path(), config_path.as_path())
+                .refresh_patches(4)
+                .await?;
+            build_spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+        }
 
         log::info!(
             "Starting build process for entry file: {}",
@@ -74,9 +87,25 @@ impl BuildCommand {
         } else {
             CompilerManager::new().await?
         };
+        compiler_manager.set_checksum_overrides(config.get_compiler_checksums());
+
+        let compiler_version = compiler_manager
+            .resolve_version(&config.build.compiler_version, &lock_path, false)
+            .await?;
+
+        if verbose && compiler_version.version != config.build.compiler_version {
+            println!(
+                "Resolved compiler {} to {}",
+                config.build.compiler_version, compiler_version.version
+            );
+        }
 
         let compiler_path = compiler_manager
-            .get_compiler_path(&config.build.compiler_version, force_download)
+            .get_compiler_path(
+                &compiler_version.version,
+                compiler_version.source.as_deref(),
+                force_download,
+            )
             .await?;
 
         if verbose {
@@ -93,10 +122,8 @@ impl BuildCommand {
         result
     }
 
-    async fn load_build_config(&self, config_path: Option<String>) -> Result<BuildConfig> {
-        let config_file = config_path.unwrap_or_else(|| "opencli.toml".to_string());
-
-        if !Path::new(&config_file).exists() {
+    async fn load_build_config(&self, config_file: &str) -> Result<BuildConfig> {
+        if !Path::new(config_file).exists() {
             return Err(OpenCliError::NotFound(
                 format!(
                     "Configuration file '{}' not found. Run 'opencli setup' to create it.",
@@ -106,7 +133,7 @@ impl BuildCommand {
             ));
         }
 
-        BuildConfig::from_file(&config_file).await
+        BuildConfig::from_file(config_file).await
     }
 
     async fn compile_project(
@@ -0,0 +1,146 @@
+use crate::cache::PackageCache;
+use crate::result::Result;
+
+pub async fn execute_list() -> Result<()> {
+    let mut cmd = CacheCommand::new();
+    cmd.execute_list().await
+}
+
+pub async fn execute_size() -> Result<()> {
+    let mut cmd = CacheCommand::new();
+    cmd.execute_size().await
+}
+
+pub async fn execute_clean(package: Option<&str>) -> Result<()> {
+    let mut cmd = CacheCommand::new();
+    cmd.execute_clean(package).await
+}
+
+pub async fn execute_verify() -> Result<()> {
+    let mut cmd = CacheCommand::new();
+    cmd.execute_verify().await
+}
+
+#[derive(Default)]
+pub struct CacheCommand;
+
+impl CacheCommand {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn execute_list(&mut self) -> Result<()> {
+        let cache = PackageCache::new()?;
+        let entries = cache.list().await?;
+
+        if entries.is_empty() {
+            println!("Package cache is empty");
+            return Ok(());
+        }
+
+        println!("Cached package assets:");
+        let mut current_repo = "";
+        for entry in &entries {
+            if entry.repo != current_repo {
+                println!("  {}", entry.repo);
+                current_repo = &entry.repo;
+            }
+            println!(
+                "    {:<14} {:<40} {:>10}  sha256:{}",
+                entry.tag,
+                entry.asset,
+                format_size(entry.size),
+                &entry.sha256[..12.min(entry.sha256.len())]
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn execute_size(&mut self) -> Result<()> {
+        let cache = PackageCache::new()?;
+        let (count, total) = cache.size().await?;
+        let entries = cache.list().await?.len();
+
+        println!("Package cache: {}", cache.base_dir().display());
+        println!(
+            "  {} asset(s), {} unique blob(s), {}",
+            entries,
+            count,
+            format_size(total)
+        );
+
+        Ok(())
+    }
+
+    pub async fn execute_clean(&mut self, package: Option<&str>) -> Result<()> {
+        let cache = PackageCache::new()?;
+        let (removed, freed) = cache.clean(package).await?;
+
+        match package {
+            Some(package) => println!(
+                "Removed {} cached blob(s) of {} ({})",
+                removed,
+                package,
+                format_size(freed)
+            ),
+            None => println!(
+                "Removed {} cached blob(s) ({})",
+                removed,
+                format_size(freed)
+            ),
+        }
+        log::info!("Package cache cleaned: {} blobs, {} bytes", removed, freed);
+
+        Ok(())
+    }
+
+    pub async fn execute_verify(&mut self) -> Result<()> {
+        let cache = PackageCache::new()?;
+        println!("Verifying package cache...");
+        let report = cache.verify().await?;
+
+        for entry in &report.corrupt {
+            println!(
+                "  Corrupt: {} {} {} (removed)",
+                entry.repo, entry.tag, entry.asset
+            );
+        }
+        for entry in &report.missing {
+            println!(
+                "  Missing: {} {} {} (removed from index)",
+                entry.repo, entry.tag, entry.asset
+            );
+        }
+
+        if report.corrupt.is_empty() && report.missing.is_empty() {
+            println!("All {} cached asset(s) are valid", report.valid);
+        } else {
+            println!(
+                "{} valid, {} corrupt, {} missing",
+                report.valid,
+                report.corrupt.len(),
+                report.missing.len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
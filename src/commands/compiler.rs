@@ -0,0 +1,207 @@
+use crate::build::BuildConfig;
+use crate::compiler::CompilerManager;
+use crate::package::version::{Version, VersionConstraint};
+use crate::package::PackageLock;
+use crate::result::{OpenCliError, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::Path;
+
+pub async fn execute_list(remote: bool) -> Result<()> {
+    let mut cmd = CompilerCommand::new();
+    cmd.execute_list(remote).await
+}
+
+pub async fn execute_update() -> Result<()> {
+    let mut cmd = CompilerCommand::new();
+    cmd.execute_update().await
+}
+
+#[derive(Default)]
+pub struct CompilerCommand;
+
+impl CompilerCommand {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn execute_list(&mut self, remote: bool) -> Result<()> {
+        let manager = CompilerManager::new().await?;
+        let project_version = self.project_compiler_version().await;
+
+        if !remote {
+            let installed = manager.list_installed().await?;
+
+            if installed.is_empty() {
+                println!("No compilers installed");
+                println!("Run 'opencli compiler list --remote' to see available versions");
+                return Ok(());
+            }
+
+            println!("Installed compilers:");
+            for version in installed {
+                let marker = if project_version.as_deref() == Some(version.as_str()) {
+                    " (project)"
+                } else {
+                    ""
+                };
+                println!("  {}{}", version, marker);
+            }
+
+            return Ok(());
+        }
+
+        let spinner = ProgressBar::new_spinner();
+        spinner.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} {msg}")
+                .unwrap(),
+        );
+        spinner.set_message("Fetching compiler releases...");
+        spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+
+        let compilers = manager.list_remote().await;
+        spinner.finish_and_clear();
+        let compilers = compilers?;
+
+        if compilers.is_empty() {
+            println!("No compiler releases found");
+            return Ok(());
+        }
+
+        let newest_stable = compilers
+            .iter()
+            .find(|c| c.is_stable() && c.platform_asset.is_some())
+            .map(|c| c.version.clone());
+
+        println!("Available compilers ({}):", std::env::consts::OS);
+        for compiler in &compilers {
+            let mut flags = Vec::new();
+            if compiler.installed {
+                flags.push("installed");
+            }
+            if compiler.prerelease {
+                flags.push("pre-release");
+            }
+            if project_version.as_deref() == Some(compiler.version.as_str()) {
+                flags.push("project");
+            }
+            if newest_stable.as_deref() == Some(compiler.version.as_str()) {
+                flags.push("newest stable");
+            }
+
+            let asset = compiler
+                .platform_asset
+                .as_deref()
+                .unwrap_or("no asset for this platform");
+
+            let flags = if flags.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", flags.join(", "))
+            };
+
+            println!(
+                "  {:<12} {:<26} {}{}",
+                compiler.version, compiler.source, asset, flags
+            );
+        }
+
+        if let Some(newest) = &newest_stable {
+            println!();
+            match &project_version {
+                Some(current) => {
+                    let newer = match (Version::parse(newest), Version::parse(current)) {
+                        (Ok(newest), Ok(current)) => newest > current,
+                        _ => false,
+                    };
+
+                    if newer {
+                        println!(
+                            "Newest stable compiler is {} (project uses {})",
+                            newest, current
+                        );
+                    } else {
+                        println!("Project compiler {} is up to date", current);
+                    }
+                }
+                None => println!("Newest stable compiler is {}", newest),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn execute_update(&mut self) -> Result<()> {
+        let config_file = "opencli.toml";
+
+        if !Path::new(config_file).exists() {
+            return Err(OpenCliError::NotFound(
+                "Configuration file 'opencli.toml' not found. Run 'opencli setup' to create it."
+                    .into(),
+            ));
+        }
+
+        let config = BuildConfig::from_file(config_file).await?;
+        let spec = config.build.compiler_version.as_str();
+        let lock_path = Path::new(config_file).with_extension("lock");
+
+        if !matches!(
+            VersionConstraint::parse(spec),
+            Ok(constraint) if !matches!(constraint, VersionConstraint::Exact(_))
+        ) {
+            println!("compiler_version is pinned to {}, nothing to update", spec);
+            return Ok(());
+        }
+
+        let previous = PackageLock::load_from_file(&lock_path)
+            .await?
+            .get_locked_compiler(spec)
+            .map(|locked| locked.version.to_string());
+
+        let mut manager = CompilerManager::new().await?;
+        manager.set_checksum_overrides(config.get_compiler_checksums());
+
+        let resolved = manager.resolve_version(spec, &lock_path, true).await?;
+        let path = manager
+            .get_compiler_path(&resolved.version, resolved.source.as_deref(), false)
+            .await?;
+        let resolved = resolved.version;
+
+        match previous {
+            Some(previous) if previous != resolved => {
+                println!("Updated compiler {}: {} -> {}", spec, previous, resolved)
+            }
+            Some(_) => println!("Compiler {} is up to date ({})", spec, resolved),
+            None => println!("Locked compiler {} to {}", spec, resolved),
+        }
+        println!("Compiler installed at: {}", path.display());
+        log::info!("Compiler {} updated to {}", spec, resolved);
+
+        Ok(())
+    }
+
+    async fn project_compiler_version(&self) -> Option<String> {
+        let config_file = "opencli.toml";
+
+        if !Path::new(config_file).exists() {
+            return None;
+        }
+
+        let spec = BuildConfig::from_file(config_file)
+            .await
+            .ok()?
+            .build
+            .compiler_version;
+
+        let lock_path = Path::new(config_file).with_extension("lock");
+        let locked = PackageLock::load_from_file(&lock_path)
+            .await
+            .ok()
+            .and_then(|lock| {
+                lock.get_locked_compiler(&spec)
+                    .map(|locked| locked.version.to_string())
+            });
+
+        Some(locked.unwrap_or(spec))
+    }
+}
@@ -1,3 +1,4 @@
+use crate::build::BuildConfig;
 use crate::compiler::CompilerManager;
 use crate::result::{OpenCliError, Result};
 use indicatif::{ProgressBar, ProgressStyle};
@@ -32,9 +33,14 @@ impl InstallCommand {
         install_spinner.enable_steady_tick(std::time::Duration::from_millis(120));
 
         let mut manager = CompilerManager::new().await?;
+
+        if std::path::Path::new("opencli.toml").exists() {
+            let config = BuildConfig::from_file("opencli.toml").await?;
+            manager.set_checksum_overrides(config.get_compiler_checksums());
+        }
         install_spinner.finish_and_clear();
 
-        match manager.get_compiler_path(&version, force).await {
+        match manager.get_compiler_path(&version, None, force).await {
             Ok(path) => {
                 println!("\nCompleted successfully!");
                 println!("Compiler installed at: {}", path.display());
@@ -1,10 +1,12 @@
 pub mod build;
+pub mod cache;
+pub mod compiler;
 pub mod install;
 pub mod run;
 pub mod setup;
 
-use crate::cli::PackageAction;
-use crate::result::Result;
+use crate::cli::{CacheAction, CompilerAction, PackageAction};
+use crate::result::{OpenCliError, Result};
 use smol_str::SmolStr;
 
 #[derive(Debug)]
@@ -25,6 +27,16 @@ pub enum CommandType {
         version: Option<SmolStr>,
         force: bool,
     },
+    ListCompilers {
+        remote: bool,
+    },
+    UpdateCompiler,
+    ListCache,
+    CacheSize,
+    CleanCache {
+        package: Option<SmolStr>,
+    },
+    VerifyCache,
 }
 
 impl CommandType {
@@ -41,6 +53,12 @@ impl CommandType {
             CommandType::InstallCompiler { version, force } => {
                 install::execute_compiler(version.as_deref(), force).await
             }
+            CommandType::ListCompilers { remote } => compiler::execute_list(remote).await,
+            CommandType::UpdateCompiler => compiler::execute_update().await,
+            CommandType::ListCache => cache::execute_list().await,
+            CommandType::CacheSize => cache::execute_size().await,
+            CommandType::CleanCache { package } => cache::execute_clean(package.as_deref()).await,
+            CommandType::VerifyCache => cache::execute_verify().await,
         }
     }
 }
@@ -91,17 +109,72 @@ impl CommandExecutor {
         .await
     }
 
+    pub async fn handle_compiler_action(&mut self, action: CompilerAction) -> Result<()> {
+        match action {
+            CompilerAction::List { remote } => {
+                CommandType::ListCompilers { remote }.execute().await
+            }
+            CompilerAction::Update => CommandType::UpdateCompiler.execute().await,
+        }
+    }
+
+    pub async fn handle_cache_action(&mut self, action: CacheAction) -> Result<()> {
+        match action {
+            CacheAction::List => CommandType::ListCache.execute().await,
+            CacheAction::Size => CommandType::CacheSize.execute().await,
+            CacheAction::Clean { package } => {
+                CommandType::CleanCache {
+                    package: package.map(|s| s.into()),
+                }
+                .execute()
+                .await
+            }
+            CacheAction::Verify => CommandType::VerifyCache.execute().await,
+        }
+    }
+
     pub async fn handle_package_action(&mut self, action: PackageAction) -> Result<()> {
         use crate::build::PackageTarget;
         use crate::package::PackageManager;
+        use crate::utils::platform::parse_platforms;
 
         let workspace_root = std::env::current_dir()?;
         let config_path = workspace_root.join("opencli.toml");
         let mut manager = PackageManager::new(&workspace_root, &config_path);
 
+        if let PackageAction::Install {
+            platform: Some(platform),
+            ..
+        }
+        | PackageAction::Sync {
+            platform: Some(platform),
+            ..
+        }
+        | PackageAction::Update {
+            platform: Some(platform),
+            ..
+        } = &action
+        {
+            manager.set_platforms(parse_platforms(platform)?);
+        }
+
         match action {
-            PackageAction::Install { package, target } => {
-                if let Some(package_spec) = package {
+            PackageAction::Install {
+                package,
+                target,
+                jobs,
+                locked,
+                frozen,
+                ..
+            } => {
+                if locked || frozen {
+                    if package.is_some() {
+                        return Err(OpenCliError::Config(
+                            "--locked and --frozen install every package from opencli.lock and take no package argument".into(),
+                        ));
+                    }
+                    manager.install_locked(frozen, jobs).await
+                } else if let Some(package_spec) = package {
                     let (repo, version) = if let Some(pos) = package_spec.find('=') {
                         let repo_part = &package_spec[..pos];
                         let version_part = &package_spec[pos + 1..];
@@ -122,17 +195,27 @@ impl CommandExecutor {
 
                     manager.install_package(repo, version, target_type).await
                 } else {
-                    manager.install_all_packages().await
+                    manager.install_all_packages(jobs).await
                 }
             }
+            PackageAction::Sync { dry_run, jobs, .. } => manager.sync_packages(dry_run, jobs).await,
             PackageAction::Remove { package } => manager.remove_package(&package).await,
             PackageAction::List => manager.list_packages().await,
             PackageAction::Check => manager.check_packages().await,
-            PackageAction::Update { package, all } => {
-                if all {
-                    manager.install_all_packages().await
-                } else if let Some(repo) = package {
-                    manager.update_package(&repo).await
+            PackageAction::Tree => manager.print_tree().await,
+            PackageAction::Why { target } => manager.explain(&target).await,
+            PackageAction::Outdated { json } => manager.outdated_packages(json).await,
+            PackageAction::Update {
+                package,
+                all,
+                dry_run,
+                jobs,
+                ..
+            } => {
+                if all || package.is_some() {
+                    manager
+                        .update_packages(package.as_deref(), dry_run, jobs)
+                        .await
                 } else {
                     println!("Specify a package to update or use --all");
                     Ok(())
@@ -1,4 +1,5 @@
 use crate::result::{OpenCliError, Result};
+use crate::utils::download::DownloadManager;
 use std::path::Path;
 use tokio::fs;
 
@@ -54,27 +55,8 @@ impl SetupCommand {
     async fn download_config(&self) -> Result<String> {
         const CONFIG_URL: &str = "https://gist.githubusercontent.com/mxp96/82fd1b1b17ccb23a11fcbe40b83ceaa5/raw/opencli.toml";
 
-        let client = reqwest::Client::new();
-        let response = client
-            .get(CONFIG_URL)
-            .header("User-Agent", "opencli/0.1.0")
-            .send()
+        DownloadManager::new()
+            .fetch_text(CONFIG_URL, "opencli.toml template")
             .await
-            .map_err(|e| {
-                OpenCliError::Process(format!("Failed to download config: {}", e).into())
-            })?;
-
-        if !response.status().is_success() {
-            return Err(OpenCliError::Process(
-                format!("Failed to download config: HTTP {}", response.status()).into(),
-            ));
-        }
-
-        let content = response
-            .text()
-            .await
-            .map_err(|e| OpenCliError::Process(format!("Failed to read config: {}", e).into()))?;
-
-        Ok(content)
     }
 }
@@ -1,4 +1,5 @@
 use crate::result::{OpenCliError, Result};
+use crate::utils::platform::Platform;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tokio::fs;
@@ -8,6 +9,15 @@ pub struct CompilerConfig {
     pub darwin: Option<PlatformConfig>,
     pub linux: Option<PlatformConfig>,
     pub windows: Option<PlatformConfig>,
+    #[serde(default = "default_sources")]
+    pub sources: Vec<String>,
+}
+
+fn default_sources() -> Vec<String> {
+    vec![
+        "openmultiplayer/compiler".to_string(),
+        "pawn-lang/compiler".to_string(),
+    ]
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +27,8 @@ pub struct PlatformConfig {
     pub method: String,
     pub binary: String,
     pub paths: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub checksums: HashMap<String, String>,
 }
 
 impl CompilerConfig {
@@ -39,14 +51,16 @@ impl CompilerConfig {
     }
 
     pub fn get_platform_config(&self) -> Option<&PlatformConfig> {
-        if cfg!(target_os = "windows") {
-            self.windows.as_ref()
-        } else if cfg!(target_os = "linux") {
-            self.linux.as_ref()
-        } else if cfg!(target_os = "macos") {
-            self.darwin.as_ref()
-        } else {
-            None
+        match Platform::current()? {
+            Platform::Windows => self.windows.as_ref(),
+            Platform::Linux => self.linux.as_ref(),
+            Platform::Macos => self.darwin.as_ref(),
         }
     }
 }
+
+impl PlatformConfig {
+    pub fn expected_checksum(&self, asset_name: &str) -> Option<&str> {
+        self.checksums.get(asset_name).map(|s| s.as_str())
+    }
+}
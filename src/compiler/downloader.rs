@@ -1,13 +1,10 @@
 use crate::result::{OpenCliError, Result};
-use indicatif::{ProgressBar, ProgressStyle};
+use crate::utils::download::{ensure_online, DownloadManager, DownloadOptions, DownloadedFile};
 use octocrab::Octocrab;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use reqwest::Client;
 use std::collections::HashMap;
 use std::path::Path;
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
 
 static REGEX_CACHE: Lazy<HashMap<&'static str, Regex>> = Lazy::new(|| {
     let mut cache = HashMap::new();
@@ -20,7 +17,7 @@ static REGEX_CACHE: Lazy<HashMap<&'static str, Regex>> = Lazy::new(|| {
 
 pub struct CompilerDownloader {
     github: std::sync::Arc<Octocrab>,
-    client: Client,
+    downloads: DownloadManager,
 }
 
 impl Default for CompilerDownloader {
@@ -31,8 +28,6 @@ impl Default for CompilerDownloader {
 
 impl CompilerDownloader {
     pub fn new() -> Self {
-        let client = Client::new();
-
         let github = if let Ok(token) = std::env::var("GITHUB_TOKEN") {
             if !token.is_empty() {
                 let crab = Octocrab::builder()
@@ -48,15 +43,23 @@ impl CompilerDownloader {
             octocrab::instance()
         };
 
-        Self { github, client }
+        Self {
+            github,
+            downloads: DownloadManager::new(),
+        }
     }
 
-    pub async fn get_release_assets(&self, version: &str) -> Result<Vec<GitHubAsset>> {
-        let (owner, repo) = if version == "v3.10.11" {
-            ("openmultiplayer", "compiler")
-        } else {
-            ("pawn-lang", "compiler")
-        };
+    /// Assets of release `version` in `source` (`owner/repo`)
+    pub async fn get_release_assets(
+        &self,
+        source: &str,
+        version: &str,
+    ) -> Result<Vec<GitHubAsset>> {
+        ensure_online(&format!("compiler {} (not installed)", version))?;
+
+        let (owner, repo) = source.split_once('/').ok_or_else(|| {
+            OpenCliError::Config(format!("Invalid compiler source: {}", source).into())
+        })?;
 
         let release = self
             .github
@@ -66,7 +69,9 @@ impl CompilerDownloader {
             .await
             .map_err(|e| match e {
                 octocrab::Error::GitHub { source, .. } if source.status_code.as_u16() == 404 => {
-                    OpenCliError::NotFound(format!("Release {} not found", version).into())
+                    OpenCliError::NotFound(
+                        format!("Release {} not found in {}", version, source).into(),
+                    )
                 }
                 _ => OpenCliError::Process(format!("Failed to fetch release info: {}", e).into()),
             })?;
@@ -83,6 +88,50 @@ impl CompilerDownloader {
         Ok(assets)
     }
 
+    pub async fn list_releases(&self, source: &str) -> Result<Vec<CompilerRelease>> {
+        ensure_online(&format!("compiler releases from {}", source))?;
+
+        let (owner, repo) = source.split_once('/').ok_or_else(|| {
+            OpenCliError::Config(format!("Invalid compiler source: {}", source).into())
+        })?;
+
+        let first_page = self
+            .github
+            .repos(owner, repo)
+            .releases()
+            .list()
+            .per_page(100u8)
+            .send()
+            .await
+            .map_err(|e| {
+                OpenCliError::Process(
+                    format!("Failed to fetch releases from {}: {}", source, e).into(),
+                )
+            })?;
+
+        let releases = self.github.all_pages(first_page).await.map_err(|e| {
+            OpenCliError::Process(format!("Failed to fetch releases from {}: {}", source, e).into())
+        })?;
+
+        Ok(releases
+            .into_iter()
+            .filter(|release| !release.draft)
+            .map(|release| CompilerRelease {
+                tag_name: release.tag_name,
+                source: source.to_string(),
+                prerelease: release.prerelease,
+                assets: release
+                    .assets
+                    .into_iter()
+                    .map(|asset| GitHubAsset {
+                        name: asset.name,
+                        download_url: asset.browser_download_url.to_string(),
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
     pub async fn find_matching_asset<'a>(
         &self,
         assets: &'a [GitHubAsset],
@@ -103,67 +152,25 @@ impl CompilerDownloader {
             })
     }
 
-    pub async fn download_asset(&self, asset: &GitHubAsset, output_path: &Path) -> Result<()> {
-        if let Some(parent) = output_path.parent() {
-            tokio::fs::create_dir_all(parent).await?;
-        }
-
-        let response = self
-            .client
-            .get(&asset.download_url)
-            .header("User-Agent", "opencli/0.1.0")
-            .send()
-            .await
-            .map_err(|e| {
-                OpenCliError::Process(format!("Failed to download asset: {}", e).into())
-            })?;
-
-        if !response.status().is_success() {
-            return Err(OpenCliError::Process(
-                format!("Download failed with status: {}", response.status()).into(),
-            ));
-        }
-
-        let total_size = response.content_length();
-
-        let pb = if let Some(size) = total_size {
-            let pb = ProgressBar::new(size);
-            pb.set_style(ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-                .unwrap()
-                .progress_chars("#>-"));
-            pb.set_message("Downloading compiler");
-            pb
-        } else {
-            let pb = ProgressBar::new_spinner();
-            pb.set_style(
-                ProgressStyle::default_spinner()
-                    .template(
-                        "{spinner:.green} [{elapsed_precise}] Downloading compiler... {bytes}",
-                    )
-                    .unwrap(),
-            );
-            pb.enable_steady_tick(std::time::Duration::from_millis(100));
-            pb
+    pub async fn download_asset(
+        &self,
+        asset: &GitHubAsset,
+        output_path: &Path,
+        expected_sha256: Option<&str>,
+    ) -> Result<DownloadedFile> {
+        let options = DownloadOptions {
+            expected_sha256: expected_sha256.map(|s| s.to_string()),
+            ..Default::default()
         };
 
-        let bytes = response
-            .bytes()
+        self.downloads
+            .download(
+                &asset.download_url,
+                output_path,
+                &format!("compiler asset {}", asset.name),
+                &options,
+            )
             .await
-            .map_err(|e| OpenCliError::Process(format!("Download failed: {}", e).into()))?;
-
-        if total_size.is_some() {
-            pb.set_position(bytes.len() as u64);
-        } else {
-            pb.inc(bytes.len() as u64);
-        }
-
-        let mut file = File::create(output_path).await?;
-        file.write_all(&bytes).await?;
-        file.flush().await?;
-
-        pb.finish_with_message(format!("Download complete ({} bytes)", bytes.len()));
-        Ok(())
     }
 }
 
@@ -172,3 +179,11 @@ pub struct GitHubAsset {
     pub name: String,
     pub download_url: String,
 }
+
+#[derive(Debug, Clone)]
+pub struct CompilerRelease {
+    pub tag_name: String,
+    pub source: String,
+    pub prerelease: bool,
+    pub assets: Vec<GitHubAsset>,
+}
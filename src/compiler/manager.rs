@@ -1,10 +1,18 @@
 use crate::cache::CacheManager;
-use crate::compiler::{CompilerConfig, CompilerDownloader, PlatformConfig};
+use crate::compiler::{
+    CompilerConfig, CompilerDownloader, CompilerVerifier, GitHubAsset, PlatformConfig,
+};
+use crate::config::UserConfig;
+use crate::package::version::{Version, VersionConstraint};
+use crate::package::PackageLock;
 use crate::result::{OpenCliError, Result};
 use crate::security::SecurityManager;
-use dirs::config_dir;
+use crate::utils::archive::{ArchiveExtractor, ArchiveFormat};
+use crate::utils::download::{is_offline, DownloadManager};
 use indicatif::{ProgressBar, ProgressStyle};
 use regex::Regex;
+use smol_str::SmolStr;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
@@ -17,6 +25,7 @@ pub struct CompilerManager {
     base_dir: PathBuf,
     security: SecurityManager,
     cache: CacheManager,
+    checksum_overrides: HashMap<String, String>,
 }
 
 impl CompilerManager {
@@ -27,7 +36,17 @@ impl CompilerManager {
         fs::create_dir_all(&base_dir).await?;
 
         let config = if config_path.exists() {
-            CompilerConfig::from_file(&config_path.to_string_lossy()).await?
+            match Self::load_cached_config(&config_path).await {
+                Ok(config) => config,
+                Err(e) if !is_offline() => {
+                    log::warn!(
+                        "Cached compilers.toml rejected ({}), downloading it again",
+                        e
+                    );
+                    Self::download_compilers_config(&config_path).await?
+                }
+                Err(e) => return Err(e),
+            }
         } else {
             Self::download_compilers_config(&config_path).await?
         };
@@ -38,6 +57,7 @@ impl CompilerManager {
             base_dir: base_dir.clone(),
             security: SecurityManager::new(),
             cache: CacheManager::new(&base_dir),
+            checksum_overrides: HashMap::new(),
         })
     }
 
@@ -56,56 +76,311 @@ impl CompilerManager {
             base_dir: base_dir.clone(),
             security: SecurityManager::new(),
             cache: CacheManager::new(&base_dir),
+            checksum_overrides: HashMap::new(),
         })
     }
 
-    fn get_base_directory() -> Result<PathBuf> {
-        let config_dir = config_dir()
-            .ok_or_else(|| OpenCliError::Config("Could not determine config directory".into()))?;
+    pub fn is_installed(&self, version: &str) -> bool {
+        self.config
+            .get_platform_config()
+            .map(|platform_config| {
+                self.base_dir
+                    .join("compilers")
+                    .join(version)
+                    .join(&platform_config.binary)
+                    .exists()
+            })
+            .unwrap_or(false)
+    }
 
-        Ok(config_dir.join("opencli"))
+    pub async fn list_installed(&self) -> Result<Vec<String>> {
+        let compilers_dir = self.base_dir.join("compilers");
+        let mut versions = Vec::new();
+
+        if !compilers_dir.exists() {
+            return Ok(versions);
+        }
+
+        let mut entries = fs::read_dir(&compilers_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if let Some(version) = entry.file_name().to_str() {
+                if self.is_installed(version) {
+                    versions.push(version.to_string());
+                }
+            }
+        }
+
+        sort_versions_desc(&mut versions, |v| v.as_str());
+        Ok(versions)
     }
 
-    async fn download_compilers_config(config_path: &Path) -> Result<CompilerConfig> {
-        const COMPILERS_CONFIG_URL: &str = "https://gist.githubusercontent.com/mxp96/798edeb8da39c7997948a9432d6f61bb/raw/compilers.toml";
+    pub async fn list_remote(&self) -> Result<Vec<RemoteCompiler>> {
+        let platform_config = self
+            .config
+            .get_platform_config()
+            .ok_or_else(|| OpenCliError::Config("Unsupported platform".into()))?;
 
-        let client = reqwest::Client::new();
-        let response = client
-            .get(COMPILERS_CONFIG_URL)
-            .header("User-Agent", "opencli/0.1.0")
-            .send()
-            .await
-            .map_err(|e| {
-                OpenCliError::Process(format!("Failed to download compilers config: {}", e).into())
-            })?;
+        let mut compilers = Vec::new();
+        let mut last_error = None;
+        let mut listed_any = false;
+
+        // One broken or rate limited source should not hide the others
+        for source in &self.config.sources {
+            let releases = match self.downloader.list_releases(source).await {
+                Ok(releases) => releases,
+                Err(e) => {
+                    log::warn!("Could not list compilers from {}: {}", source, e);
+                    eprintln!("Warning: skipping compiler source {}: {}", source, e);
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            listed_any = true;
+
+            for release in releases {
+                let platform_asset = self
+                    .downloader
+                    .find_matching_asset(&release.assets, &platform_config.match_pattern)
+                    .await
+                    .ok()
+                    .map(|asset| asset.name.clone());
+
+                compilers.push(RemoteCompiler {
+                    installed: self.is_installed(&release.tag_name),
+                    version: release.tag_name,
+                    source: release.source,
+                    prerelease: release.prerelease,
+                    platform_asset,
+                });
+            }
+        }
 
-        if !response.status().is_success() {
-            return Err(OpenCliError::Process(
-                format!(
-                    "Failed to download compilers config: HTTP {}",
-                    response.status()
-                )
-                .into(),
-            ));
+        if let (false, Some(e)) = (listed_any, last_error) {
+            return Err(e);
         }
 
-        let content = response.text().await.map_err(|e| {
-            OpenCliError::Process(format!("Failed to read compilers config: {}", e).into())
-        })?;
+        sort_versions_desc(&mut compilers, |c| c.version.as_str());
+        Ok(compilers)
+    }
+
+    pub async fn resolve_version(
+        &self,
+        spec: &str,
+        lock_path: &Path,
+        refresh: bool,
+    ) -> Result<ResolvedCompiler> {
+        let constraint = match VersionConstraint::parse(spec) {
+            Ok(VersionConstraint::Exact(_)) | Err(_) => {
+                return Ok(ResolvedCompiler {
+                    version: spec.to_string(),
+                    source: None,
+                })
+            }
+            Ok(constraint) => constraint,
+        };
+
+        let mut lock = PackageLock::load_from_file(lock_path).await?;
+
+        if !refresh {
+            if let Some(locked) = lock.get_locked_compiler(spec) {
+                let still_matches = Version::parse(&locked.version)
+                    .map(|v| constraint.matches(&v))
+                    .unwrap_or(false);
+
+                if still_matches {
+                    log::info!("Using locked compiler {} for {}", locked.version, spec);
+                    return Ok(ResolvedCompiler {
+                        version: locked.version.to_string(),
+                        source: locked.source.as_ref().map(|s| s.to_string()),
+                    });
+                }
+            }
+        }
+
+        // Installed compilers have no known source; a remote listing of the same tag adds it
+        let mut candidates: Vec<(String, Option<String>)> = self
+            .list_installed()
+            .await?
+            .into_iter()
+            .map(|version| (version, None))
+            .collect();
+
+        if is_offline() {
+            log::info!("Offline mode: resolving {} from installed compilers", spec);
+        } else {
+            match self.list_remote().await {
+                Ok(remote) => candidates.extend(
+                    remote
+                        .into_iter()
+                        .filter(|c| c.is_stable() && c.platform_asset.is_some())
+                        .map(|c| (c.version, Some(c.source))),
+                ),
+                Err(e) => {
+                    log::warn!("Could not list remote compilers: {}", e);
+                    eprintln!(
+                    "Warning: could not list remote compilers, resolving from installed versions only"
+                );
+                }
+            }
+        }
 
-        fs::write(config_path, &content).await?;
+        let resolved = candidates
+            .into_iter()
+            .filter_map(|(tag, source)| {
+                let version = Version::parse(&tag).ok()?;
+                (version.suffix.is_empty() && constraint.matches(&version))
+                    .then_some((version, tag, source))
+            })
+            .max_by(|(a, _, a_source), (b, _, b_source)| {
+                a.cmp(b).then(a_source.is_some().cmp(&b_source.is_some()))
+            })
+            .map(|(_, version, source)| ResolvedCompiler { version, source })
+            .ok_or_else(|| {
+                OpenCliError::NotFound(
+                    format!("No compiler version matches constraint {}", spec).into(),
+                )
+            })?;
+
+        lock.set_compiler(
+            spec.into(),
+            resolved.version.as_str().into(),
+            resolved.source.as_deref().map(SmolStr::from),
+        );
+        lock.save_to_file(lock_path).await?;
+
+        log::info!("Resolved compiler {} to {}", spec, resolved.version);
+        Ok(resolved)
+    }
+
+    pub fn set_checksum_overrides(&mut self, checksums: HashMap<String, String>) {
+        self.checksum_overrides = checksums;
+    }
+
+    fn get_base_directory() -> Result<PathBuf> {
+        UserConfig::base_directory()
+    }
 
-        // Parse the config
+    async fn download_compilers_config(config_path: &Path) -> Result<CompilerConfig> {
+        const COMPILERS_CONFIG_URL: &str = "https://gist.githubusercontent.com/mxp96/798edeb8da39c7997948a9432d6f61bb/raw/compilers.toml";
+
+        if is_offline() && config_path.exists() {
+            eprintln!("Warning: offline mode, using cached compilers.toml");
+            return Self::load_cached_config(config_path).await;
+        }
+
+        let downloads = DownloadManager::new();
+        let content = downloads
+            .fetch_text(COMPILERS_CONFIG_URL, "compilers config")
+            .await?;
+
+        // Parse the config before trusting it
         let config: CompilerConfig = toml::from_str(&content).map_err(|e| {
             OpenCliError::Config(format!("Invalid compilers config format: {}", e).into())
         })?;
 
+        let signature_path = config_path.with_extension("toml.minisig");
+        let mut signature = None;
+
+        if let Some(public_key) = Self::compilers_config_public_key().await? {
+            let signature_url = format!("{}.minisig", COMPILERS_CONFIG_URL);
+            let verified = match downloads
+                .fetch_text(&signature_url, "compilers config signature")
+                .await
+            {
+                Ok(sig) => SecurityManager::new()
+                    .verify_minisign(content.as_bytes(), &sig, &public_key)
+                    .map(|_| sig),
+                Err(e) => Err(e),
+            };
+
+            match verified {
+                Ok(sig) => {
+                    log::info!("compilers.toml signature verified");
+                    signature = Some(sig);
+                }
+                Err(e) => {
+                    log::error!("Rejected remote compilers.toml: {}", e);
+
+                    if config_path.exists() {
+                        eprintln!(
+                            "Warning: rejected remote compilers.toml ({}), keeping previous copy",
+                            e
+                        );
+                        return Self::load_cached_config(config_path).await;
+                    }
+
+                    return Err(OpenCliError::Config(
+                        format!("Rejected remote compilers.toml: {}", e).into(),
+                    ));
+                }
+            }
+        } else {
+            // Verification is opt-in until a key is embedded or configured
+            eprintln!(
+                "Warning: compilers.toml is not signature checked; set [compilers] public_key in the opencli config to verify it"
+            );
+        }
+
+        let temp_path = config_path.with_extension("toml.tmp");
+        fs::write(&temp_path, &content).await?;
+        fs::rename(&temp_path, config_path).await?;
+
+        match signature {
+            Some(sig) => fs::write(&signature_path, sig).await?,
+            // A signature of an older copy would no longer match
+            None if signature_path.exists() => fs::remove_file(&signature_path).await?,
+            None => {}
+        }
+
         Ok(config)
     }
 
+    /** Reads the cached compilers.toml
+     *
+     * When a public key is configured the copy must still match the `compilers.toml.minisig`
+     * saved next to it, so a file edited on disk is not trusted either.
+     */
+    async fn load_cached_config(config_path: &Path) -> Result<CompilerConfig> {
+        if let Some(public_key) = Self::compilers_config_public_key().await? {
+            let content = fs::read_to_string(config_path).await?;
+            let signature_path = config_path.with_extension("toml.minisig");
+            let signature = fs::read_to_string(&signature_path).await.map_err(|_| {
+                OpenCliError::Config(
+                    format!(
+                        "Cached compilers.toml has no signature at {}",
+                        signature_path.display()
+                    )
+                    .into(),
+                )
+            })?;
+
+            SecurityManager::new().verify_minisign(content.as_bytes(), &signature, &public_key)?;
+        }
+
+        CompilerConfig::from_file(&config_path.to_string_lossy()).await
+    }
+
+    async fn compilers_config_public_key() -> Result<Option<String>> {
+        const EMBEDDED_PUBLIC_KEY: Option<&str> = option_env!("OPENCLI_COMPILERS_PUBLIC_KEY");
+
+        let user_config = UserConfig::load().await?;
+
+        Ok(user_config
+            .compilers
+            .public_key
+            .filter(|key| !key.trim().is_empty())
+            .or_else(|| EMBEDDED_PUBLIC_KEY.map(|key| key.to_string())))
+    }
+
+    /** Returns the binary of an installed compiler, downloading it first if needed
+     *
+     * # Arguments
+     * * `source` - Repository of the release, every configured source is tried when `None`
+     */
     pub async fn get_compiler_path(
         &mut self,
         version: &str,
+        source: Option<&str>,
         force_download: bool,
     ) -> Result<PathBuf> {
         let platform_config = self
@@ -116,6 +391,24 @@ impl CompilerManager {
         let compiler_dir = self.base_dir.join("compilers").join(version);
         let binary_path = compiler_dir.join(&platform_config.binary);
 
+        if binary_path.exists() && is_offline() && !force_download {
+            // Nothing to re-download from, so trust the install unless its hash is known to differ
+            if let Ok(Some(cached_hash)) = self.cache.get_hash(&platform_config.binary).await {
+                if let Ok(false) = self.security.verify_file(&binary_path, &cached_hash).await {
+                    return Err(OpenCliError::Process(
+                        format!(
+                            "Offline mode: compiler {} failed hash verification and cannot be re-downloaded",
+                            version
+                        )
+                        .into(),
+                    ));
+                }
+            }
+
+            log::info!("Offline mode: using installed compiler {}", version);
+            return Ok(binary_path);
+        }
+
         if binary_path.exists() && !force_download {
             if let Ok(Some(cached_hash)) = self.cache.get_hash(&platform_config.binary).await {
                 match self.security.verify_file(&binary_path, &cached_hash).await {
@@ -133,7 +426,7 @@ impl CompilerManager {
             }
         }
 
-        self.download_and_install_compiler(version, platform_config)
+        self.download_and_install_compiler(version, source, platform_config)
             .await?;
 
         if binary_path.exists() {
@@ -168,9 +461,38 @@ impl CompilerManager {
         }
     }
 
+    /// Assets of `version` from `source`, or from the first configured source that has it
+    async fn release_assets(
+        &self,
+        version: &str,
+        source: Option<&str>,
+    ) -> Result<Vec<GitHubAsset>> {
+        if let Some(source) = source {
+            return self.downloader.get_release_assets(source, version).await;
+        }
+
+        for source in &self.config.sources {
+            match self.downloader.get_release_assets(source, version).await {
+                Ok(assets) => return Ok(assets),
+                Err(OpenCliError::NotFound(_)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(OpenCliError::NotFound(
+            format!(
+                "Compiler release {} not found in {}",
+                version,
+                self.config.sources.join(", ")
+            )
+            .into(),
+        ))
+    }
+
     async fn download_and_install_compiler(
         &self,
         version: &str,
+        source: Option<&str>,
         platform_config: &PlatformConfig,
     ) -> Result<()> {
         let spinner = ProgressBar::new_spinner();
@@ -183,7 +505,7 @@ impl CompilerManager {
         spinner.set_message("Fetching release information...");
         spinner.enable_steady_tick(std::time::Duration::from_millis(100));
 
-        let assets = self.downloader.get_release_assets(version).await?;
+        let assets = self.release_assets(version, source).await?;
         let matching_asset = self
             .downloader
             .find_matching_asset(&assets, &platform_config.match_pattern)
@@ -198,9 +520,20 @@ impl CompilerManager {
         println!("Downloading version {}", version);
 
         let downloaded_file = temp_dir.join(&matching_asset.name);
-        self.downloader
-            .download_asset(matching_asset, &downloaded_file)
-            .await?;
+        let expected = self.expected_asset_checksum(&matching_asset.name, platform_config);
+        let downloaded = self
+            .downloader
+            .download_asset(matching_asset, &downloaded_file, expected.as_deref())
+            .await
+            .inspect_err(|e| log::error!("Failed to download {}: {}", matching_asset.name, e))?;
+
+        if expected.is_some() {
+            log::info!(
+                "Compiler asset {} matches pinned sha256 {}",
+                matching_asset.name,
+                downloaded.sha256
+            );
+        }
 
         let extract_spinner = ProgressBar::new_spinner();
         extract_spinner.set_style(
@@ -214,15 +547,14 @@ impl CompilerManager {
         let extract_dir = self.base_dir.join("compilers").join(version);
         fs::create_dir_all(&extract_dir).await?;
 
-        match platform_config.method.as_str() {
-            "zip" => self.extract_zip(&downloaded_file, &extract_dir).await?,
-            "tgz" => self.extract_tgz(&downloaded_file, &extract_dir).await?,
-            _ => {
-                return Err(OpenCliError::Config(
-                    format!("Unsupported extraction method: {}", platform_config.method).into(),
-                ))
-            }
-        }
+        let format = ArchiveFormat::from_method(&platform_config.method).ok_or_else(|| {
+            OpenCliError::Config(
+                format!("Unsupported extraction method: {}", platform_config.method).into(),
+            )
+        })?;
+        ArchiveExtractor::new()
+            .extract_as(format, &downloaded_file, &extract_dir)
+            .await?;
 
         extract_spinner.set_message("Organizing files...");
         self.organize_files(&extract_dir, platform_config).await?;
@@ -230,54 +562,51 @@ impl CompilerManager {
         extract_spinner.set_message("Cleaning up temporary files...");
         fs::remove_file(&downloaded_file).await?;
 
-        extract_spinner.finish_and_clear();
-
-        Ok(())
-    }
-
-    async fn extract_zip(&self, archive_path: &Path, extract_to: &Path) -> Result<()> {
-        let file = std::fs::File::open(archive_path)
-            .map_err(|e| OpenCliError::Process(format!("Failed to open zip file: {}", e).into()))?;
-
-        let mut archive = zip::ZipArchive::new(file).map_err(|e| {
-            OpenCliError::Process(format!("Failed to read zip archive: {}", e).into())
-        })?;
-
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i).map_err(|e| {
-                OpenCliError::Process(format!("Failed to read zip entry: {}", e).into())
-            })?;
-
-            let outpath = extract_to.join(file.name());
-
-            if file.name().ends_with('/') {
-                fs::create_dir_all(&outpath).await?;
-            } else {
-                if let Some(parent) = outpath.parent() {
-                    fs::create_dir_all(parent).await?;
-                }
+        let binary_path = extract_dir.join(&platform_config.binary);
+        if !binary_path.exists() {
+            extract_spinner.finish_and_clear();
+            fs::remove_dir_all(&extract_dir).await?;
+            return Err(OpenCliError::NotFound(
+                format!(
+                    "{} does not contain the compiler binary {}; check the binary setting for this platform in compilers.toml",
+                    matching_asset.name, platform_config.binary
+                )
+                .into(),
+            ));
+        }
 
-                let mut buffer = Vec::new();
-                std::io::copy(&mut file, &mut buffer).map_err(|e| {
-                    OpenCliError::Process(format!("Failed to read file: {}", e).into())
-                })?;
-                tokio::fs::write(&outpath, buffer).await?;
-            }
+        extract_spinner.set_message("Verifying compiler...");
+        if let Err(e) = CompilerVerifier::new().verify(&binary_path).await {
+            extract_spinner.finish_and_clear();
+            log::error!("Compiler {} failed verification: {}", version, e);
+            fs::remove_dir_all(&extract_dir).await?;
+            return Err(e);
         }
 
+        extract_spinner.finish_and_clear();
+
         Ok(())
     }
 
-    async fn extract_tgz(&self, archive_path: &Path, extract_to: &Path) -> Result<()> {
-        let file = tokio::fs::File::open(archive_path).await?;
-        let decoder = flate2::read::GzDecoder::new(file.into_std().await);
-        let mut archive = tar::Archive::new(decoder);
-
-        archive.unpack(extract_to).map_err(|e| {
-            OpenCliError::Process(format!("Failed to extract tar.gz: {}", e).into())
-        })?;
+    fn expected_asset_checksum(
+        &self,
+        asset_name: &str,
+        platform_config: &PlatformConfig,
+    ) -> Option<String> {
+        let expected = self
+            .checksum_overrides
+            .get(asset_name)
+            .map(|s| s.as_str())
+            .or_else(|| platform_config.expected_checksum(asset_name));
+
+        if expected.is_none() {
+            log::warn!(
+                "No pinned SHA-256 checksum for compiler asset {}, skipping verification",
+                asset_name
+            );
+        }
 
-        Ok(())
+        expected.map(|s| s.to_string())
     }
 
     async fn organize_files(
@@ -374,3 +703,37 @@ impl CompilerManager {
         Ok(())
     }
 }
+
+/// Compiler version picked for a `compiler_version` constraint
+#[derive(Debug, Clone)]
+pub struct ResolvedCompiler {
+    pub version: String,
+    /// Repository of the release, `None` when only an installed copy matched
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoteCompiler {
+    pub version: String,
+    pub source: String,
+    pub prerelease: bool,
+    pub platform_asset: Option<String>,
+    pub installed: bool,
+}
+
+impl RemoteCompiler {
+    pub fn is_stable(&self) -> bool {
+        !self.prerelease
+            && Version::parse(&self.version)
+                .map(|v| v.suffix.is_empty())
+                .unwrap_or(false)
+    }
+}
+
+fn sort_versions_desc<T>(items: &mut [T], version_of: impl Fn(&T) -> &str) {
+    items.sort_by(|a, b| {
+        let a = Version::parse(version_of(a)).ok();
+        let b = Version::parse(version_of(b)).ok();
+        b.cmp(&a)
+    });
+}
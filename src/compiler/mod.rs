@@ -1,7 +1,9 @@
 pub mod config;
 pub mod downloader;
 pub mod manager;
+pub mod verifier;
 
 pub use config::*;
 pub use downloader::*;
 pub use manager::*;
+pub use verifier::*;
@@ -0,0 +1,258 @@
+use crate::result::{OpenCliError, Result};
+use std::path::Path;
+use std::process::Stdio;
+use tokio::fs;
+use tokio::process::Command;
+
+const SMOKE_TEST_SOURCE: &str = "main(){}\n";
+
+// Header layout: size(4) magic(2) file_version(1) amx_version(1) flags(2)
+// defsize(2) followed by eleven 32-bit offsets
+const AMX_HEADER_SIZE: usize = 56;
+
+// Magic values for 32-bit, 64-bit and 16-bit cell builds
+const AMX_MAGICS: [u16; 3] = [0xF1E0, 0xF1E1, 0xF1E2];
+
+#[derive(Default)]
+pub struct CompilerVerifier;
+
+impl CompilerVerifier {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn verify(&self, binary_path: &Path) -> Result<()> {
+        let work_dir = std::env::temp_dir()
+            .join("opencli")
+            .join(format!("compiler-smoke-{}", std::process::id()));
+        fs::create_dir_all(&work_dir).await?;
+
+        let result = self.run_smoke_test(binary_path, &work_dir).await;
+
+        if let Err(e) = fs::remove_dir_all(&work_dir).await {
+            log::warn!("Failed to remove smoke test directory: {}", e);
+        }
+
+        result
+    }
+
+    async fn run_smoke_test(&self, binary_path: &Path, work_dir: &Path) -> Result<()> {
+        let source_path = work_dir.join("main.pwn");
+        let output_path = work_dir.join("main.amx");
+        fs::write(&source_path, SMOKE_TEST_SOURCE).await?;
+
+        let mut cmd = Command::new(binary_path);
+        cmd.current_dir(work_dir);
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        if let Some(compiler_dir) = binary_path.parent() {
+            let var = if cfg!(target_os = "macos") {
+                "DYLD_LIBRARY_PATH"
+            } else {
+                "LD_LIBRARY_PATH"
+            };
+
+            if !cfg!(windows) {
+                let mut lib_path = compiler_dir.to_string_lossy().to_string();
+                if let Ok(existing) = std::env::var(var) {
+                    lib_path = format!("{}:{}", lib_path, existing);
+                }
+                cmd.env(var, lib_path);
+            }
+        }
+
+        cmd.arg(format!("-o{}", output_path.display()));
+        cmd.arg(&source_path);
+
+        let output = cmd
+            .output()
+            .await
+            .map_err(|e| self.spawn_error(binary_path, e))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let combined = format!("{}{}", stdout, stderr);
+
+        if !output.status.success() {
+            return Err(self.exit_error(output.status.code(), &combined));
+        }
+
+        if !output_path.exists() {
+            return Err(OpenCliError::Process(
+                format!(
+                    "Compiler smoke test failed: {} exited successfully but produced no AMX file. \
+                     The archive may contain an incompatible compiler build.",
+                    binary_path.display()
+                )
+                .into(),
+            ));
+        }
+
+        let amx = fs::read(&output_path).await?;
+        self.check_amx_header(&amx)?;
+
+        log::info!(
+            "Compiler smoke test passed: {} produced {} byte AMX",
+            binary_path.display(),
+            amx.len()
+        );
+
+        Ok(())
+    }
+
+    fn spawn_error(&self, binary_path: &Path, e: std::io::Error) -> OpenCliError {
+        const ENOEXEC: i32 = 8;
+
+        let hint = if e.raw_os_error() == Some(ENOEXEC) {
+            format!(
+                "the binary is not executable on this machine ({} {}). \
+                 The downloaded archive is probably built for another architecture; \
+                 check the 'match' pattern in compilers.toml.",
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            )
+        } else if e.kind() == std::io::ErrorKind::NotFound && binary_path.exists() {
+            "the binary exists but its program loader is missing. \
+             It is probably a 32-bit build; install the 32-bit runtime \
+             (e.g. 'libc6:i386' on Debian/Ubuntu) or use a 64-bit compiler release."
+                .to_string()
+        } else if e.kind() == std::io::ErrorKind::PermissionDenied {
+            "the binary is not executable. Check file permissions and noexec mounts.".to_string()
+        } else {
+            e.to_string()
+        };
+
+        OpenCliError::Process(
+            format!(
+                "Compiler smoke test failed for {}: {}",
+                binary_path.display(),
+                hint
+            )
+            .into(),
+        )
+    }
+
+    fn exit_error(&self, code: Option<i32>, output: &str) -> OpenCliError {
+        let missing_library = output
+            .lines()
+            .find(|line| {
+                line.contains("error while loading shared libraries")
+                    || line.contains("Library not loaded")
+            })
+            .map(|line| line.trim().to_string());
+
+        let message = if let Some(line) = missing_library {
+            format!(
+                "Compiler smoke test failed: a shared library is missing ({}). \
+                 Make sure the library shipped with the compiler was extracted next to the binary \
+                 or install it system-wide, then run 'opencli install compiler --force'.",
+                line
+            )
+        } else if code == Some(127) {
+            "Compiler smoke test failed: the compiler could not be started (exit code 127). \
+             A shared library or the program loader is probably missing."
+                .to_string()
+        } else {
+            format!(
+                "Compiler smoke test failed: compiling an empty script exited with code {}.\n{}",
+                code.unwrap_or(-1),
+                output.trim()
+            )
+        };
+
+        OpenCliError::Process(message.into())
+    }
+
+    fn check_amx_header(&self, amx: &[u8]) -> Result<()> {
+        if amx.len() < AMX_HEADER_SIZE {
+            return Err(OpenCliError::Process(
+                format!(
+                    "Compiler smoke test failed: AMX output is truncated ({} bytes)",
+                    amx.len()
+                )
+                .into(),
+            ));
+        }
+
+        let size = u32::from_le_bytes([amx[0], amx[1], amx[2], amx[3]]) as usize;
+        let magic = u16::from_le_bytes([amx[4], amx[5]]);
+        let defsize = u16::from_le_bytes([amx[10], amx[11]]);
+
+        if !AMX_MAGICS.contains(&magic) {
+            return Err(OpenCliError::Process(
+                format!(
+                    "Compiler smoke test failed: AMX output has invalid magic 0x{:04X}",
+                    magic
+                )
+                .into(),
+            ));
+        }
+
+        if size < AMX_HEADER_SIZE || size > amx.len() || defsize == 0 {
+            return Err(OpenCliError::Process(
+                format!(
+                    "Compiler smoke test failed: AMX header is inconsistent (size {}, file {} bytes)",
+                    size,
+                    amx.len()
+                )
+                .into(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(size: u32, magic: u16, len: usize) -> Vec<u8> {
+        let mut amx = vec![0u8; len];
+        amx[0..4].copy_from_slice(&size.to_le_bytes());
+        amx[4..6].copy_from_slice(&magic.to_le_bytes());
+        amx[6] = 8;
+        amx[7] = 8;
+        amx[10..12].copy_from_slice(&8u16.to_le_bytes());
+        amx
+    }
+
+    #[test]
+    fn accepts_a_valid_header() {
+        let verifier = CompilerVerifier::new();
+        for magic in AMX_MAGICS {
+            assert!(verifier.check_amx_header(&header(120, magic, 120)).is_ok());
+        }
+        // Trailing bytes after the image are allowed
+        assert!(verifier.check_amx_header(&header(120, 0xF1E0, 128)).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_short_buffer() {
+        let amx = header(120, 0xF1E0, 120);
+        let error = CompilerVerifier::new()
+            .check_amx_header(&amx[..AMX_HEADER_SIZE - 1])
+            .unwrap_err();
+        assert!(error.to_string().contains("truncated"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_wrong_magic() {
+        let error = CompilerVerifier::new()
+            .check_amx_header(&header(120, 0x1234, 120))
+            .unwrap_err();
+        assert!(error.to_string().contains("0x1234"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_size_that_disagrees_with_the_buffer() {
+        let verifier = CompilerVerifier::new();
+        for size in [200, (AMX_HEADER_SIZE - 1) as u32] {
+            let error = verifier
+                .check_amx_header(&header(size, 0xF1E0, 120))
+                .unwrap_err();
+            assert!(error.to_string().contains("inconsistent"), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::result::{OpenCliError, Result};
+use dirs::config_dir;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::fs;
+
+/** User-level OpenCLI settings shared by every project
+ *
+ * Stored next to `compilers.toml` in the OpenCLI config directory:
+ * - Linux: `~/.config/opencli/config.toml`
+ * - macOS: `~/Library/Application Support/opencli/config.toml`
+ * - Windows: `%APPDATA%\opencli\config.toml`
+ *
+ * # Example
+ * ```toml
+ * [compilers]
+ * public_key = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"
+ * ```
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserConfig {
+    #[serde(default)]
+    pub compilers: CompilersSettings,
+}
+
+/** Settings for the remote compiler configuration */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompilersSettings {
+    /// Minisign public key used to verify `compilers.toml` signatures
+    pub public_key: Option<String>,
+}
+
+impl UserConfig {
+    /** Returns the OpenCLI config directory
+     *
+     * # Errors
+     * - `OpenCliError::Config` if the platform config directory is unknown
+     */
+    pub fn base_directory() -> Result<PathBuf> {
+        let config_dir = config_dir()
+            .ok_or_else(|| OpenCliError::Config("Could not determine config directory".into()))?;
+
+        Ok(config_dir.join("opencli"))
+    }
+
+    /** Loads the user config, falling back to defaults when the file is missing
+     *
+     * # Errors
+     * - `OpenCliError::Io` if the file exists but cannot be read
+     * - `OpenCliError::Config` if the file is not valid TOML
+     */
+    pub async fn load() -> Result<Self> {
+        let path = Self::base_directory()?.join("config.toml");
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).await?;
+        toml::from_str(&content).map_err(|e| {
+            OpenCliError::Config(format!("Invalid user config {}: {}", path.display(), e).into())
+        })
+    }
+}
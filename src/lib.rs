@@ -11,6 +11,7 @@
 /// - cache: File-based caching system with integrity validation
 /// - cli: Command-line interface parsing and execution
 /// - commands: Implementation of build commands and subcommands
+/// - config: User-level settings shared across projects
 /// - compiler: Compiler abstraction and toolchain management
 /// - package: Package configuration and manifest handling
 /// - result: Error handling and result types
@@ -21,6 +22,7 @@ pub mod cache;
 pub mod cli;
 pub mod commands;
 pub mod compiler;
+pub mod config;
 pub mod package;
 pub mod result;
 pub mod security;
@@ -1,17 +1,25 @@
+use crate::build::PackageSpec;
+use crate::cache::PackageCache;
+use crate::package::lock::{LockedAsset, PackageDependency};
+use crate::package::manifest::{PackageManifest, MANIFEST_FILE};
+use crate::package::source::{is_commit, pinned_release, GitSource, PackageSource, GITLAB_HOST};
 use crate::package::version::{Version, VersionConstraint};
 use crate::result::{OpenCliError, Result};
-use flate2::read::GzDecoder;
-use indicatif::{ProgressBar, ProgressStyle};
+use crate::security::SecurityManager;
+use crate::utils::archive::{ArchiveExtractor, ArchiveFormat, ExtractedFile};
+use crate::utils::download::{ensure_online, is_offline, DownloadManager, DownloadOptions};
+use crate::utils::platform::{platform_list, Platform};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use indicatif::ProgressBar;
 use octocrab::Octocrab;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use reqwest::Client;
-use std::io::Read;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use smol_str::SmolStr;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use tar::Archive;
-use tokio::fs::{create_dir_all, File};
-use tokio::io::AsyncWriteExt;
-use zip::ZipArchive;
+use tokio::fs::create_dir_all;
 
 static GITHUB_REPO_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^([^/]+)/([^/]+)$").unwrap());
 
@@ -24,12 +32,15 @@ static AMX_LIB_REGEX: Lazy<Regex> =
 
 pub struct PackageDownloader {
     github: std::sync::Arc<Octocrab>,
-    client: Client,
+    downloads: DownloadManager,
+    cache: Option<PackageCache>,
 }
 
 #[derive(Debug, Clone)]
 pub struct GitHubRelease {
     pub tag_name: String,
+    pub body: Option<String>,
+    pub prerelease: bool,
     pub assets: Vec<GitHubAsset>,
 }
 
@@ -38,15 +49,33 @@ pub struct GitHubAsset {
     pub name: String,
     pub download_url: String,
     pub size: u64,
+    pub digest: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+/// Which assets and files of a release get installed
+#[derive(Debug, Clone, Default)]
+pub struct PackageSelection {
+    pub target: Option<crate::build::config::PackageTarget>,
+    /// Only assets and binaries for these platforms are kept, all when empty
+    pub platforms: Vec<Platform>,
+    /// Globs of the assets to download; they replace the platform check when set
+    pub asset_patterns: Vec<SmolStr>,
+    /// Globs of downloaded files that are not installed
+    pub excluded_files: Vec<SmolStr>,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct PackageFiles {
     pub includes: Vec<PathBuf>,
     pub binaries: Vec<PathBuf>,
     pub root_binaries: Vec<PathBuf>,
     pub component_binaries: Vec<PathBuf>,
     pub plugin_binaries: Vec<PathBuf>,
+    pub assets: Vec<LockedAsset>,
+    /// Classified by opencli-package.toml rather than the path heuristics
+    pub from_manifest: bool,
+    /// Minimum compiler version declared by the package manifest
+    pub min_compiler: Option<SmolStr>,
 }
 
 impl Default for PackageDownloader {
@@ -57,8 +86,6 @@ impl Default for PackageDownloader {
 
 impl PackageDownloader {
     pub fn new() -> Self {
-        let client = Client::new();
-
         let github = if let Ok(token) = std::env::var("GITHUB_TOKEN") {
             if !token.is_empty() {
                 let crab = Octocrab::builder()
@@ -74,26 +101,41 @@ impl PackageDownloader {
             octocrab::instance()
         };
 
-        Self { github, client }
+        Self {
+            github,
+            downloads: DownloadManager::new(),
+            cache: PackageCache::new()
+                .inspect_err(|e| log::warn!("Package cache unavailable: {}", e))
+                .ok(),
+        }
     }
 
     pub async fn get_releases(&self, repo: &str) -> Result<Vec<GitHubRelease>> {
         let (owner, repo_name) = self.parse_repo(repo)?;
 
-        let releases = self
+        if is_offline() {
+            return self.cached_releases(repo).await;
+        }
+
+        let first_page = self
             .github
             .repos(owner, repo_name)
             .releases()
             .list()
+            .per_page(100u8)
             .send()
             .await
             .map_err(|e| {
                 OpenCliError::Process(format!("Failed to fetch releases: {}", e).into())
             })?;
 
+        let releases = self.github.all_pages(first_page).await.map_err(|e| {
+            OpenCliError::Process(format!("Failed to fetch releases: {}", e).into())
+        })?;
+
         let mut github_releases = Vec::new();
 
-        for release in releases.items {
+        for release in releases {
             let assets = release
                 .assets
                 .into_iter()
@@ -101,11 +143,14 @@ impl PackageDownloader {
                     name: asset.name,
                     download_url: asset.browser_download_url.to_string(),
                     size: asset.size as u64,
+                    digest: asset.digest,
                 })
                 .collect();
 
             github_releases.push(GitHubRelease {
                 tag_name: release.tag_name,
+                body: release.body,
+                prerelease: release.prerelease,
                 assets,
             });
         }
@@ -113,6 +158,50 @@ impl PackageDownloader {
         Ok(github_releases)
     }
 
+    async fn cached_releases(&self, repo: &str) -> Result<Vec<GitHubRelease>> {
+        let Some(cache) = &self.cache else {
+            ensure_online(&format!("releases of {}", repo))?;
+            return Ok(Vec::new());
+        };
+
+        let releases: Vec<GitHubRelease> = cache
+            .releases(repo)
+            .await?
+            .into_iter()
+            .map(|(tag_name, entries)| GitHubRelease {
+                tag_name,
+                body: None,
+                prerelease: false,
+                assets: entries
+                    .into_iter()
+                    .map(|entry| GitHubAsset {
+                        name: entry.asset,
+                        download_url: entry.url,
+                        size: entry.size,
+                        digest: Some(format!("sha256:{}", entry.sha256)),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        if releases.is_empty() {
+            return Err(OpenCliError::NotFound(
+                format!(
+                    "Offline mode: no cached releases of {}. Install it once while online to warm the cache.",
+                    repo
+                )
+                .into(),
+            ));
+        }
+
+        log::info!(
+            "Offline mode: resolving {} from {} cached release(s)",
+            repo,
+            releases.len()
+        );
+        Ok(releases)
+    }
+
     pub async fn find_matching_version(
         &self,
         repo: &str,
@@ -120,56 +209,508 @@ impl PackageDownloader {
     ) -> Result<GitHubRelease> {
         let releases = self.get_releases(repo).await?;
 
-        let versions: Vec<(Version, &GitHubRelease)> = releases
+        Self::latest_release(&releases, constraint)
+            .cloned()
+            .ok_or_else(|| {
+                OpenCliError::NotFound("No matching version found for constraint".into())
+            })
+    }
+
+    /// Picks the newest release whose tag satisfies `constraint`
+    pub fn latest_release<'a>(
+        releases: &'a [GitHubRelease],
+        constraint: &VersionConstraint,
+    ) -> Option<&'a GitHubRelease> {
+        let versions: Vec<Version> = releases
             .iter()
-            .filter_map(|release| Version::parse(&release.tag_name).ok().map(|v| (v, release)))
+            .filter_map(|release| Version::parse(&release.tag_name).ok())
             .collect();
 
-        let version_refs: Vec<&Version> = versions.iter().map(|(v, _)| v).collect();
-        if let Some(matched_version) =
-            constraint.latest_matching(&version_refs.iter().map(|&v| v.clone()).collect::<Vec<_>>())
-        {
-            if let Some((_, release)) = versions.iter().find(|(ver, _)| ver == matched_version) {
-                Ok((*release).clone())
-            } else {
-                Err(OpenCliError::NotFound(
-                    "No matching version found for constraint"
-                        .to_string()
-                        .into(),
+        let matched = constraint.latest_matching(&versions)?;
+        releases
+            .iter()
+            .find(|release| Version::parse(&release.tag_name).ok().as_ref() == Some(matched))
+    }
+
+    /** Resolves the ref of a git source to a full commit SHA
+     *
+     * A full SHA in `rev` is used as is, so pinned commits resolve offline.
+     * Remotes other than GitHub are asked with `git ls-remote`.
+     */
+    pub async fn resolve_commit(&self, source: &GitSource) -> Result<SmolStr> {
+        if let Some(rev) = source.rev.as_deref().filter(|rev| is_commit(rev)) {
+            return Ok(rev.to_lowercase().into());
+        }
+
+        ensure_online(&format!("commit of {}", source))?;
+        if !source.is_github() {
+            return self.ls_remote(source).await;
+        }
+        let (owner, repo_name) = self.parse_repo(&source.git)?;
+
+        let commit: CommitRef = self
+            .github
+            .get(
+                format!(
+                    "/repos/{}/{}/commits/{}",
+                    owner,
+                    repo_name,
+                    source.reference()
+                ),
+                None::<&()>,
+            )
+            .await
+            .map_err(|e| {
+                OpenCliError::NotFound(format!("Failed to resolve {}: {}", source, e).into())
+            })?;
+
+        Ok(commit.sha)
+    }
+
+    async fn ls_remote(&self, source: &GitSource) -> Result<SmolStr> {
+        let patterns = match (&source.branch, &source.tag) {
+            (Some(branch), _) => vec![format!("refs/heads/{}", branch)],
+            (None, Some(tag)) => vec![
+                format!("refs/tags/{}", tag),
+                format!("refs/tags/{}^{{}}", tag),
+            ],
+            (None, None) => vec!["HEAD".to_string()],
+        };
+
+        let mut args = vec!["ls-remote", "--", source.git.as_str()];
+        args.extend(patterns.iter().map(String::as_str));
+        let output = run_git(&args, None, &format!("resolve {}", source)).await?;
+
+        // Annotated tags list the tag object first and the commit it points to as `^{}`
+        let refs: Vec<(&str, &str)> = output
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .collect();
+        refs.iter()
+            .find(|(_, name)| name.ends_with("^{}"))
+            .or_else(|| refs.first())
+            .map(|(sha, _)| SmolStr::from(sha.trim()))
+            .ok_or_else(|| OpenCliError::NotFound(format!("{} not found", source).into()))
+    }
+
+    /** Resolves a pinned source to the version recorded in the lock
+     *
+     * Git sources lock their commit, URLs the first 12 characters of their
+     * SHA-256 and local paths a hash of their contents.
+     */
+    pub async fn resolve_pinned(&self, source: &PackageSource) -> Result<SmolStr> {
+        match source {
+            PackageSource::Git(git) => self.resolve_commit(git).await,
+            PackageSource::Url { sha256, .. } => Ok(sha256[..12].into()),
+            PackageSource::Path { .. } => {
+                let path = source.local_path().unwrap_or_default();
+                local_version(&path).await
+            }
+            PackageSource::GitLab { .. } | PackageSource::Gitea { .. } => Err(
+                OpenCliError::Config(format!("{} is picked by version", source).into()),
+            ),
+        }
+    }
+
+    /// Releases of a package from GitHub, or from the GitLab or Gitea project it names
+    pub async fn releases_for(
+        &self,
+        repo: &str,
+        source: Option<&PackageSource>,
+    ) -> Result<Vec<GitHubRelease>> {
+        let Some(source) = source else {
+            return self.get_releases(repo).await;
+        };
+        if let Some(github_repo) = source.github_repo() {
+            return self.get_releases(github_repo).await;
+        }
+        if !source.is_versioned() {
+            return Ok(Vec::new());
+        }
+
+        if is_offline() {
+            return self.cached_releases(repo).await;
+        }
+
+        match source {
+            PackageSource::GitLab { gitlab, host } => {
+                self.gitlab_releases(gitlab, host.as_deref().unwrap_or(GITLAB_HOST), source)
+                    .await
+            }
+            PackageSource::Gitea { gitea, host } => self.gitea_releases(gitea, host, source).await,
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// The release to download for a resolved version
+    pub async fn release_for(
+        &self,
+        repo: &str,
+        source: Option<&PackageSource>,
+        version: &str,
+    ) -> Result<GitHubRelease> {
+        match source {
+            None => {
+                self.find_matching_version(repo, &VersionConstraint::parse(version)?)
+                    .await
+            }
+            Some(PackageSource::Git(git)) => Ok(git.release(version)),
+            Some(PackageSource::Url { url, sha256 }) => {
+                let name = url
+                    .split(['?', '#'])
+                    .next()
+                    .and_then(|url| url.rsplit('/').next())
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or("package");
+
+                Ok(pinned_release(
+                    version,
+                    vec![GitHubAsset {
+                        name: name.to_string(),
+                        download_url: url.to_string(),
+                        size: 0,
+                        digest: Some(format!("sha256:{}", sha256)),
+                    }],
                 ))
             }
+            Some(PackageSource::Path { .. }) => Ok(pinned_release(version, Vec::new())),
+            Some(source) => self
+                .releases_for(repo, Some(source))
+                .await?
+                .into_iter()
+                .find(|release| release.tag_name == version)
+                .ok_or_else(|| {
+                    OpenCliError::NotFound(
+                        format!("Release {} of {} not found", version, source).into(),
+                    )
+                }),
+        }
+    }
+
+    /** Fetches every page of a GitLab or Gitea list endpoint
+     *
+     * Servers may cap the page size below the one asked for, so the first page
+     * sets the size and the list ends at the first shorter page.
+     */
+    async fn fetch_all_pages<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        what: &str,
+        headers: &[(String, String)],
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut page_size = None;
+
+        for page in 1.. {
+            let batch: Vec<T> = self
+                .downloads
+                .fetch_json(&format!("{}&page={}", url, page), what, headers)
+                .await?;
+            let size = *page_size.get_or_insert(batch.len());
+            let last = batch.is_empty() || batch.len() < size;
+            items.extend(batch);
+            if last {
+                break;
+            }
+        }
+
+        Ok(items)
+    }
+
+    async fn gitlab_releases(
+        &self,
+        project: &str,
+        host: &str,
+        source: &PackageSource,
+    ) -> Result<Vec<GitHubRelease>> {
+        let url = format!(
+            "{}/api/v4/projects/{}/releases?per_page=100",
+            host.trim_end_matches('/'),
+            project.trim_matches('/').replace('/', "%2F")
+        );
+        let releases: Vec<GitLabRelease> = self
+            .fetch_all_pages(
+                &url,
+                &format!("releases of {}", source),
+                &auth_headers(source),
+            )
+            .await?;
+
+        let repo_name = project.rsplit('/').next().unwrap_or(project);
+
+        Ok(releases
+            .into_iter()
+            .map(|release| {
+                let mut assets: Vec<GitHubAsset> = release
+                    .assets
+                    .links
+                    .into_iter()
+                    .map(|link| GitHubAsset {
+                        name: link.name,
+                        download_url: link.direct_asset_url.unwrap_or(link.url),
+                        size: 0,
+                        digest: None,
+                    })
+                    .collect();
+
+                if assets.is_empty() {
+                    // Releases without uploads still carry the source archive
+                    assets.extend(
+                        release
+                            .assets
+                            .sources
+                            .into_iter()
+                            .find(|archive| archive.format == "tar.gz")
+                            .map(|archive| GitHubAsset {
+                                name: format!("{}-{}.tar.gz", repo_name, release.tag_name),
+                                download_url: archive.url,
+                                size: 0,
+                                digest: None,
+                            }),
+                    );
+                }
+
+                GitHubRelease {
+                    tag_name: release.tag_name,
+                    body: release.description,
+                    prerelease: release.upcoming_release,
+                    assets,
+                }
+            })
+            .collect())
+    }
+
+    async fn gitea_releases(
+        &self,
+        repo: &str,
+        host: &str,
+        source: &PackageSource,
+    ) -> Result<Vec<GitHubRelease>> {
+        let url = format!(
+            "{}/api/v1/repos/{}/releases?limit=50",
+            host.trim_end_matches('/'),
+            repo
+        );
+        let releases: Vec<GiteaRelease> = self
+            .fetch_all_pages(
+                &url,
+                &format!("releases of {}", source),
+                &auth_headers(source),
+            )
+            .await?;
+
+        let repo_name = repo.rsplit('/').next().unwrap_or(repo);
+
+        Ok(releases
+            .into_iter()
+            .map(|release| {
+                let mut assets: Vec<GitHubAsset> = release
+                    .assets
+                    .into_iter()
+                    .map(|asset| GitHubAsset {
+                        name: asset.name,
+                        download_url: asset.browser_download_url,
+                        size: asset.size,
+                        digest: None,
+                    })
+                    .collect();
+
+                if assets.is_empty() {
+                    assets.extend(release.tarball_url.map(|url| GitHubAsset {
+                        name: format!("{}-{}.tar.gz", repo_name, release.tag_name),
+                        download_url: url,
+                        size: 0,
+                        digest: None,
+                    }));
+                }
+
+                GitHubRelease {
+                    tag_name: release.tag_name,
+                    body: release.body,
+                    prerelease: release.prerelease,
+                    assets,
+                }
+            })
+            .collect())
+    }
+
+    /** Returns the packages a release depends on
+     *
+     * Reads `[packages]` from the release's opencli.toml, falling back to the
+     * `dependencies` list of a sampctl pawn.json. Results are cached per release,
+     * and a release that was never fetched has no known dependencies offline.
+     */
+    pub async fn fetch_dependencies(
+        &self,
+        repo: &str,
+        tag: &str,
+    ) -> Result<Vec<PackageDependency>> {
+        if let Some(cache) = &self.cache {
+            if let Some(dependencies) = cache.dependencies(repo, tag).await? {
+                return Ok(dependencies);
+            }
+        }
+
+        if is_offline() {
+            log::warn!(
+                "Offline mode: dependencies of {} {} are unknown and were skipped",
+                repo,
+                tag
+            );
+            return Ok(Vec::new());
+        }
+
+        let (owner, repo_name) = self.parse_repo(repo)?;
+        let raw_url = |file: &str| {
+            format!(
+                "https://raw.githubusercontent.com/{}/{}/{}/{}",
+                owner, repo_name, tag, file
+            )
+        };
+
+        let dependencies = if let Some(content) = self
+            .downloads
+            .fetch_optional_text(
+                &raw_url("opencli.toml"),
+                &format!("opencli.toml of {}", repo),
+            )
+            .await?
+        {
+            parse_opencli_dependencies(repo, &content)?
+        } else if let Some(content) = self
+            .downloads
+            .fetch_optional_text(&raw_url("pawn.json"), &format!("pawn.json of {}", repo))
+            .await?
+        {
+            parse_pawn_json_dependencies(repo, &content)?
         } else {
-            Err(OpenCliError::NotFound(
-                "No matching version found for constraint"
-                    .to_string()
-                    .into(),
-            ))
+            Vec::new()
+        };
+
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.store_dependencies(repo, tag, &dependencies).await {
+                log::warn!("Failed to cache dependencies of {} {}: {}", repo, tag, e);
+            }
         }
+
+        Ok(dependencies)
     }
 
+    /// Dependencies declared in the opencli.toml or pawn.json of a local package folder
+    pub async fn local_dependencies(
+        &self,
+        repo: &str,
+        source: &PackageSource,
+    ) -> Result<Vec<PackageDependency>> {
+        let Some(dir) = source.local_path().filter(|path| path.is_dir()) else {
+            return Ok(Vec::new());
+        };
+
+        if let Ok(content) = tokio::fs::read_to_string(dir.join("opencli.toml")).await {
+            parse_opencli_dependencies(repo, &content)
+        } else if let Ok(content) = tokio::fs::read_to_string(dir.join("pawn.json")).await {
+            parse_pawn_json_dependencies(repo, &content)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /** Downloads a package and classifies its files
+     *
+     * # Arguments
+     * * `repo` - Package name from opencli.toml
+     * * `source` - Where the package comes from, `None` for a GitHub release of `repo`
+     * * `release` - Release or pinned version to install
+     * * `selection` - Target folder and platforms to install for
+     */
     pub async fn download_package(
         &self,
         repo: &str,
+        source: Option<&PackageSource>,
         release: &GitHubRelease,
         temp_dir: &Path,
-        target: Option<&crate::build::config::PackageTarget>,
+        selection: &PackageSelection,
+        progress_bar: Option<&ProgressBar>,
     ) -> Result<PackageFiles> {
         create_dir_all(temp_dir).await?;
+        let target = selection.target.as_ref();
+        let platforms = selection.platforms.as_slice();
+        let asset_globs = package_globs(repo, "assets", &selection.asset_patterns, false)?;
+        let excluded_globs = package_globs(repo, "files.exclude", &selection.excluded_files, true)?;
+
+        let github_repo = source.map_or(Some(repo), PackageSource::github_repo);
+        let cache_repo = github_repo.unwrap_or(repo);
+        let headers = source.map(auth_headers).unwrap_or_default();
+
+        let mut package_files = PackageFiles::default();
+        // Every downloaded file with its path inside the package, for the manifest
+        let mut candidates: Vec<(PathBuf, String)> = self
+            .fetch_unpackaged(source, &release.tag_name, temp_dir, progress_bar)
+            .await?;
+        if !candidates.is_empty() {
+            let structure = candidates.iter().map(|(_, path)| path.clone()).collect();
+            package_files = self.filter_files_by_target(candidates.clone(), structure, target);
+        }
 
-        let mut package_files = PackageFiles {
-            includes: Vec::new(),
-            binaries: Vec::new(),
-            root_binaries: Vec::new(),
-            component_binaries: Vec::new(),
-            plugin_binaries: Vec::new(),
-        };
+        let assets: Vec<&GitHubAsset> = release
+            .assets
+            .iter()
+            .filter(|asset| {
+                if selection.asset_patterns.is_empty() {
+                    Platform::matches_any(&asset.name, platforms)
+                } else {
+                    asset_globs.is_match(&asset.name)
+                }
+            })
+            .collect();
+        if assets.is_empty() && !release.assets.is_empty() {
+            if !selection.asset_patterns.is_empty() {
+                return Err(OpenCliError::NotFound(
+                    format!(
+                        "No asset of {} {} matches assets = {:?}; available: {}",
+                        repo,
+                        release.tag_name,
+                        selection.asset_patterns,
+                        release
+                            .assets
+                            .iter()
+                            .map(|asset| asset.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                    .into(),
+                ));
+            }
+            log::warn!(
+                "{} {} has no assets for {}",
+                repo,
+                release.tag_name,
+                platform_list(platforms)
+            );
+        }
 
-        for asset in &release.assets {
+        for asset in assets {
             let asset_path = temp_dir.join(&asset.name);
-            self.download_asset(asset, &asset_path).await?;
+            let sha256 = self
+                .fetch_asset(
+                    cache_repo,
+                    &release.tag_name,
+                    asset,
+                    &asset_path,
+                    &headers,
+                    progress_bar,
+                )
+                .await?;
+            package_files.assets.push(LockedAsset {
+                name: asset.name.as_str().into(),
+                url: asset.download_url.as_str().into(),
+                sha256: sha256.into(),
+            });
 
             if self.is_archive(&asset.name) {
-                let extracted = self.extract_archive(&asset_path, temp_dir, target).await?;
+                let (extracted, files) =
+                    self.extract_archive(&asset_path, temp_dir, target).await?;
+                candidates.extend(files);
                 package_files.includes.extend(extracted.includes);
                 package_files.binaries.extend(extracted.binaries);
                 package_files.root_binaries.extend(extracted.root_binaries);
@@ -179,175 +720,415 @@ impl PackageDownloader {
                 package_files
                     .plugin_binaries
                     .extend(extracted.plugin_binaries);
-            } else if INCLUDE_REGEX.is_match(&asset.name) {
-                package_files.includes.push(asset_path);
-            } else if BINARY_REGEX.is_match(&asset.name) {
-                self.categorize_binary(&asset_path, &mut package_files);
+            } else {
+                candidates.push((asset_path.clone(), asset.name.clone()));
+
+                if INCLUDE_REGEX.is_match(&asset.name) {
+                    package_files.includes.push(asset_path);
+                } else if BINARY_REGEX.is_match(&asset.name) {
+                    self.categorize_binary(&asset_path, &mut package_files);
+                }
             }
         }
 
-        if package_files.includes.is_empty()
+        let empty = package_files.includes.is_empty()
             && package_files.binaries.is_empty()
             && package_files.root_binaries.is_empty()
             && package_files.component_binaries.is_empty()
-            && package_files.plugin_binaries.is_empty()
-        {
-            self.download_repo_content(repo, &release.tag_name, temp_dir, &mut package_files)
-                .await?;
+            && package_files.plugin_binaries.is_empty();
+
+        if let (true, Some(repo)) = (empty, github_repo) {
+            self.download_repo_content(
+                repo,
+                &release.tag_name,
+                temp_dir,
+                &mut package_files,
+                progress_bar,
+            )
+            .await?;
+
+            candidates.extend(
+                package_files
+                    .includes
+                    .iter()
+                    .filter_map(|path| Some((path.clone(), path.file_name()?.to_str()?.into()))),
+            );
         }
 
-        Ok(package_files)
-    }
+        let manifest = match (self.find_manifest(&candidates).await?, github_repo) {
+            (Some(found), _) => Some(found),
+            (None, Some(repo)) => self
+                .fetch_repo_manifest(repo, &release.tag_name, temp_dir, &mut package_files)
+                .await?
+                .map(|manifest| (manifest, String::new())),
+            (None, None) => None,
+        };
 
-    async fn download_asset(&self, asset: &GitHubAsset, output_path: &Path) -> Result<()> {
-        let response = self
-            .client
-            .get(&asset.download_url)
-            .header("User-Agent", "opencli/0.1.0")
-            .send()
-            .await
-            .map_err(|e| OpenCliError::Process(format!("Download failed: {}", e).into()))?;
+        if let Some((manifest, base)) = manifest {
+            log::info!("Installing {} as declared by {}", repo, MANIFEST_FILE);
+            let assets = std::mem::take(&mut package_files.assets);
+            package_files = manifest.select(&candidates, &base, target, platforms)?;
+            package_files.assets = assets;
+        }
 
-        if !response.status().is_success() {
-            return Err(OpenCliError::Process(
-                format!("Download failed: HTTP {}", response.status()).into(),
-            ));
+        if !selection.excluded_files.is_empty() {
+            let excluded: HashSet<&PathBuf> = candidates
+                .iter()
+                .filter(|(_, path)| excluded_globs.is_match(path.replace('\\', "/")))
+                .map(|(file, _)| file)
+                .collect();
+            for files in [
+                &mut package_files.includes,
+                &mut package_files.binaries,
+                &mut package_files.root_binaries,
+                &mut package_files.component_binaries,
+                &mut package_files.plugin_binaries,
+            ] {
+                files.retain(|path| !excluded.contains(path));
+            }
         }
 
-        let pb = ProgressBar::new(asset.size);
-        pb.set_style(ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-            .unwrap()
-            .progress_chars("#>-"));
-        pb.set_message(format!("Downloading {}", asset.name));
+        // Archives often bundle the .dll and .so side by side
+        for binaries in [
+            &mut package_files.binaries,
+            &mut package_files.root_binaries,
+            &mut package_files.component_binaries,
+            &mut package_files.plugin_binaries,
+        ] {
+            binaries.retain(|path| Platform::accepts_file(path, platforms));
+        }
 
-        let bytes = response
-            .bytes()
-            .await
-            .map_err(|e| OpenCliError::Process(format!("Download failed: {}", e).into()))?;
+        Ok(package_files)
+    }
 
-        pb.set_position(bytes.len() as u64);
+    /** Fetches packages that have no assets: a git checkout or a local path
+     *
+     * Returns every file with its path inside the package, or nothing for other sources.
+     */
+    async fn fetch_unpackaged(
+        &self,
+        source: Option<&PackageSource>,
+        version: &str,
+        temp_dir: &Path,
+        progress_bar: Option<&ProgressBar>,
+    ) -> Result<Vec<(PathBuf, String)>> {
+        let extracted = match source {
+            Some(PackageSource::Git(git)) if !git.is_github() => {
+                if let Some(pb) = progress_bar {
+                    pb.set_message(format!("Cloning {}", git.git));
+                }
+                self.checkout(git, version, temp_dir).await?
+            }
+            Some(source @ PackageSource::Path { .. }) => {
+                let path = source.local_path().unwrap_or_default();
+                if !path.exists() {
+                    return Err(OpenCliError::NotFound(
+                        format!("Package path {} does not exist", path.display()).into(),
+                    ));
+                }
 
-        let mut file = File::create(output_path).await?;
-        file.write_all(&bytes).await?;
-        file.flush().await?;
+                if path.is_dir() {
+                    let name = path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("package");
+                    ArchiveExtractor::new().import_directory(&path, &temp_dir.join(name))?
+                } else if self.is_archive(&path.to_string_lossy()) {
+                    ArchiveExtractor::new().extract(&path, temp_dir).await?
+                } else {
+                    let name = path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("package");
+                    tokio::fs::copy(&path, temp_dir.join(name)).await?;
+                    return Ok(vec![(temp_dir.join(name), name.to_string())]);
+                }
+            }
+            _ => return Ok(Vec::new()),
+        };
 
-        pb.finish_with_message(format!("Downloaded {}", asset.name));
-        Ok(())
+        Ok(extracted
+            .into_iter()
+            .map(|file| (file.path, file.archive_path))
+            .collect())
     }
 
-    async fn extract_archive(
+    /// Checks out `commit` of a git remote into `temp_dir/<repo name>`
+    async fn checkout(
         &self,
-        archive_path: &Path,
-        extract_dir: &Path,
-        target: Option<&crate::build::config::PackageTarget>,
-    ) -> Result<PackageFiles> {
-        let file_name = archive_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("");
-
-        if file_name.ends_with(".zip") {
-            self.extract_zip(archive_path, extract_dir, target).await
-        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
-            self.extract_tar_gz(archive_path, extract_dir, target).await
-        } else {
-            Ok(PackageFiles {
-                includes: Vec::new(),
-                binaries: Vec::new(),
-                root_binaries: Vec::new(),
-                component_binaries: Vec::new(),
-                plugin_binaries: Vec::new(),
-            })
+        git: &GitSource,
+        commit: &str,
+        temp_dir: &Path,
+    ) -> Result<Vec<ExtractedFile>> {
+        ensure_online(&format!("checkout of {}", git))?;
+        if !is_commit(commit) {
+            return Err(OpenCliError::Config(
+                format!("Invalid commit for {}: {}", git, commit).into(),
+            ));
+        }
+
+        let checkout = temp_dir.join(".checkout");
+        let what = format!("fetch {}", git);
+        run_git(
+            &["init", "-q", "--", &checkout.to_string_lossy()],
+            None,
+            &what,
+        )
+        .await?;
+
+        // Most servers serve a single commit; otherwise fetch every branch and tag
+        if run_git(
+            &["fetch", "-q", "--depth", "1", "--", &git.git, commit],
+            Some(&checkout),
+            &what,
+        )
+        .await
+        .is_err()
+        {
+            run_git(
+                &[
+                    "fetch",
+                    "-q",
+                    "--update-head-ok",
+                    "--",
+                    &git.git,
+                    "+refs/heads/*:refs/heads/*",
+                    "+refs/tags/*:refs/tags/*",
+                ],
+                Some(&checkout),
+                &what,
+            )
+            .await?;
         }
+        run_git(
+            &["-c", "advice.detachedHead=false", "checkout", "-q", commit],
+            Some(&checkout),
+            &what,
+        )
+        .await?;
+
+        let files =
+            ArchiveExtractor::new().import_directory(&checkout, &temp_dir.join(git.repo_name()));
+        if let Err(e) = tokio::fs::remove_dir_all(&checkout).await {
+            log::warn!("Failed to remove {}: {}", checkout.display(), e);
+        }
+        files
     }
 
-    async fn extract_zip(
+    /// Finds the shallowest opencli-package.toml among the downloaded files
+    async fn find_manifest(
         &self,
-        zip_path: &Path,
-        extract_dir: &Path,
-        target: Option<&crate::build::config::PackageTarget>,
-    ) -> Result<PackageFiles> {
-        let file = std::fs::File::open(zip_path)?;
-        let mut archive = ZipArchive::new(file)
-            .map_err(|e| OpenCliError::Process(format!("Invalid ZIP archive: {}", e).into()))?;
+        candidates: &[(PathBuf, String)],
+    ) -> Result<Option<(PackageManifest, String)>> {
+        let found = candidates
+            .iter()
+            .map(|(path, package_path)| (path, package_path.replace('\\', "/")))
+            .filter(|(_, package_path)| package_path.rsplit('/').next() == Some(MANIFEST_FILE))
+            .min_by_key(|(_, package_path)| package_path.matches('/').count());
 
-        let mut all_files = Vec::new();
-        let mut archive_structure = Vec::new();
+        let Some((path, package_path)) = found else {
+            return Ok(None);
+        };
 
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i).map_err(|e| {
-                OpenCliError::Process(format!("ZIP extraction error: {}", e).into())
-            })?;
+        let content = tokio::fs::read_to_string(path).await?;
+        let manifest = PackageManifest::parse(&content, &package_path)?;
+        let base = package_path
+            .rsplit_once('/')
+            .map(|(dir, _)| dir.to_string())
+            .unwrap_or_default();
 
-            let file_path = extract_dir.join(file.name());
-            archive_structure.push(file.name().to_string());
+        Ok(Some((manifest, base)))
+    }
 
-            if file.is_dir() {
-                create_dir_all(&file_path).await?;
-            } else {
-                if let Some(parent) = file_path.parent() {
-                    create_dir_all(parent).await?;
-                }
+    /// Fetches opencli-package.toml from the repository root, recording it as a locked asset
+    async fn fetch_repo_manifest(
+        &self,
+        repo: &str,
+        tag: &str,
+        temp_dir: &Path,
+        package_files: &mut PackageFiles,
+    ) -> Result<Option<PackageManifest>> {
+        let (owner, repo_name) = self.parse_repo(repo)?;
+        let url = format!(
+            "https://raw.githubusercontent.com/{}/{}/{}/{}",
+            owner, repo_name, tag, MANIFEST_FILE
+        );
+        let path = temp_dir.join(MANIFEST_FILE);
+
+        let cached = match &self.cache {
+            Some(cache) => cache
+                .restore(repo, tag, MANIFEST_FILE, None, &path)
+                .await
+                .unwrap_or_else(|e| {
+                    log::warn!("Package cache lookup failed for {}: {}", MANIFEST_FILE, e);
+                    None
+                }),
+            None => None,
+        };
 
-                let mut output = File::create(&file_path).await?;
-                let mut buffer = Vec::new();
-                file.read_to_end(&mut buffer)
-                    .map_err(|e| OpenCliError::Process(format!("ZIP read error: {}", e).into()))?;
-                output.write_all(&buffer).await?;
+        let sha256 = match cached {
+            Some(sha256) => sha256,
+            None if is_offline() => return Ok(None),
+            None => {
+                let Some(content) = self
+                    .downloads
+                    .fetch_optional_text(&url, &format!("{} of {}", MANIFEST_FILE, repo))
+                    .await?
+                else {
+                    return Ok(None);
+                };
+
+                tokio::fs::write(&path, &content).await?;
+                let sha256 = SecurityManager::new().sha256_file(&path).await?;
+
+                if let Some(cache) = &self.cache {
+                    if let Err(e) = cache
+                        .store(repo, tag, MANIFEST_FILE, &url, &path, &sha256)
+                        .await
+                    {
+                        log::warn!("Failed to add {} to package cache: {}", MANIFEST_FILE, e);
+                    }
+                }
 
-                all_files.push((file_path, file.name().to_string()));
+                sha256
             }
-        }
+        };
+
+        package_files.assets.push(LockedAsset {
+            name: MANIFEST_FILE.into(),
+            url: url.into(),
+            sha256: sha256.into(),
+        });
 
-        Ok(self.filter_files_by_target(all_files, archive_structure, target))
+        let content = tokio::fs::read_to_string(&path).await?;
+        PackageManifest::parse(&content, &format!("{} of {}", MANIFEST_FILE, repo)).map(Some)
     }
 
-    async fn extract_tar_gz(
+    async fn fetch_asset(
         &self,
-        tar_path: &Path,
-        extract_dir: &Path,
-        target: Option<&crate::build::config::PackageTarget>,
-    ) -> Result<PackageFiles> {
-        let file = std::fs::File::open(tar_path)?;
-        let decoder = GzDecoder::new(file);
-        let mut archive = Archive::new(decoder);
+        repo: &str,
+        tag: &str,
+        asset: &GitHubAsset,
+        output_path: &Path,
+        headers: &[(String, String)],
+        progress_bar: Option<&ProgressBar>,
+    ) -> Result<String> {
+        let name = asset.name.as_str();
+        let digest = asset.digest.as_deref();
+
+        if let Some(cache) = &self.cache {
+            match cache.restore(repo, tag, name, digest, output_path).await {
+                Ok(Some(sha256)) => {
+                    if let Some(pb) = progress_bar {
+                        pb.set_message(format!("{} (cached)", name));
+                    }
+                    return Ok(sha256);
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("Package cache lookup failed for {}: {}", name, e),
+            }
+        }
 
-        let mut all_files = Vec::new();
-        let mut archive_structure = Vec::new();
+        let options = DownloadOptions {
+            expected_sha256: digest
+                .and_then(|d| d.strip_prefix("sha256:"))
+                .map(|d| d.to_string()),
+            progress_bar: progress_bar.cloned(),
+            headers: headers.to_vec(),
+        };
 
-        for entry in archive
-            .entries()
-            .map_err(|e| OpenCliError::Process(format!("TAR extraction error: {}", e).into()))?
-        {
-            let mut entry = entry
-                .map_err(|e| OpenCliError::Process(format!("TAR entry error: {}", e).into()))?;
+        let downloaded = self
+            .downloads
+            .download(&asset.download_url, output_path, name, &options)
+            .await?;
+
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache
+                .store(
+                    repo,
+                    tag,
+                    name,
+                    &asset.download_url,
+                    output_path,
+                    &downloaded.sha256,
+                )
+                .await
+            {
+                log::warn!("Failed to add {} to package cache: {}", name, e);
+            }
+        }
 
-            let entry_path = entry
-                .path()
-                .map_err(|e| OpenCliError::Process(format!("TAR path error: {}", e).into()))?;
-            let file_path = extract_dir.join(&entry_path);
-            let entry_path_string = entry_path.to_str().unwrap_or("").to_string();
+        Ok(downloaded.sha256)
+    }
 
-            archive_structure.push(entry_path_string.clone());
+    /** Fetches the locked assets of a package into `temp_dir`
+     *
+     * Git remotes and local paths have no assets and are checked out or copied again.
+     */
+    pub async fn download_locked_assets(
+        &self,
+        repo: &str,
+        source: Option<&PackageSource>,
+        tag: &str,
+        assets: &[LockedAsset],
+        temp_dir: &Path,
+        progress_bar: Option<&ProgressBar>,
+    ) -> Result<()> {
+        create_dir_all(temp_dir).await?;
 
-            if entry.header().entry_type().is_file() {
-                if let Some(parent) = file_path.parent() {
-                    create_dir_all(parent).await?;
-                }
+        self.fetch_unpackaged(source, tag, temp_dir, progress_bar)
+            .await?;
+
+        let repo = source
+            .map_or(Some(repo), PackageSource::github_repo)
+            .unwrap_or(repo);
+        let headers = source.map(auth_headers).unwrap_or_default();
 
-                let mut output = File::create(&file_path).await?;
-                let mut buffer = Vec::new();
-                entry
-                    .read_to_end(&mut buffer)
-                    .map_err(|e| OpenCliError::Process(format!("TAR read error: {}", e).into()))?;
-                output.write_all(&buffer).await?;
+        for locked in assets {
+            let asset = GitHubAsset {
+                name: locked.name.to_string(),
+                download_url: locked.url.to_string(),
+                size: 0,
+                digest: Some(format!("sha256:{}", locked.sha256)),
+            };
+
+            let asset_path = temp_dir.join(&asset.name);
+            self.fetch_asset(repo, tag, &asset, &asset_path, &headers, progress_bar)
+                .await?;
 
-                all_files.push((file_path, entry_path_string));
+            if self.is_archive(&asset.name) {
+                ArchiveExtractor::new()
+                    .extract(&asset_path, temp_dir)
+                    .await?;
             }
         }
 
-        Ok(self.filter_files_by_target(all_files, archive_structure, target))
+        Ok(())
+    }
+
+    async fn extract_archive(
+        &self,
+        archive_path: &Path,
+        extract_dir: &Path,
+        target: Option<&crate::build::config::PackageTarget>,
+    ) -> Result<(PackageFiles, Vec<(PathBuf, String)>)> {
+        let extracted = ArchiveExtractor::new()
+            .extract(archive_path, extract_dir)
+            .await?;
+
+        let archive_structure = extracted
+            .iter()
+            .map(|file| file.archive_path.clone())
+            .collect();
+        let all_files: Vec<(PathBuf, String)> = extracted
+            .into_iter()
+            .map(|file| (file.path, file.archive_path))
+            .collect();
+
+        Ok((
+            self.filter_files_by_target(all_files.clone(), archive_structure, target),
+            all_files,
+        ))
     }
 
     fn filter_files_by_target(
@@ -356,13 +1137,7 @@ impl PackageDownloader {
         archive_structure: Vec<String>,
         target: Option<&crate::build::config::PackageTarget>,
     ) -> PackageFiles {
-        let mut package_files = PackageFiles {
-            includes: Vec::new(),
-            binaries: Vec::new(),
-            root_binaries: Vec::new(),
-            component_binaries: Vec::new(),
-            plugin_binaries: Vec::new(),
-        };
+        let mut package_files = PackageFiles::default();
 
         if let Some(target) = target {
             match target {
@@ -499,8 +1274,10 @@ impl PackageDownloader {
         tag: &str,
         temp_dir: &Path,
         package_files: &mut PackageFiles,
+        progress_bar: Option<&ProgressBar>,
     ) -> Result<()> {
         let (owner, repo_name) = self.parse_repo(repo)?;
+        ensure_online(&format!("include files of {} {}", repo, tag))?;
 
         let repo_obj = self.github.repos(owner, repo_name);
         let contents = repo_obj
@@ -518,17 +1295,21 @@ impl PackageDownloader {
                 if INCLUDE_REGEX.is_match(&name) {
                     let file_path = temp_dir.join(&name);
 
-                    if let Some(download_url) = &item.download_url {
-                        let response = self.client.get(download_url).send().await.map_err(|e| {
-                            OpenCliError::Process(format!("Failed to download file: {}", e).into())
-                        })?;
-
-                        let content = response.bytes().await.map_err(|e| {
-                            OpenCliError::Process(format!("Failed to read file: {}", e).into())
-                        })?;
-
-                        let mut file = File::create(&file_path).await?;
-                        file.write_all(&content).await?;
+                    if let Some(download_url) = item.download_url {
+                        let asset = GitHubAsset {
+                            name,
+                            download_url,
+                            size: item.size as u64,
+                            digest: None,
+                        };
+                        let sha256 = self
+                            .fetch_asset(repo, tag, &asset, &file_path, &[], progress_bar)
+                            .await?;
+                        package_files.assets.push(LockedAsset {
+                            name: asset.name.as_str().into(),
+                            url: asset.download_url.as_str().into(),
+                            sha256: sha256.into(),
+                        });
 
                         package_files.includes.push(file_path);
                     }
@@ -589,9 +1370,277 @@ impl PackageDownloader {
     }
 
     fn is_archive(&self, filename: &str) -> bool {
-        filename.ends_with(".zip")
-            || filename.ends_with(".tar.gz")
-            || filename.ends_with(".tgz")
-            || filename.ends_with(".rar")
+        ArchiveFormat::detect(filename).is_some()
+    }
+}
+
+// Standard libraries come with the compiler, installing them as packages would shadow it
+const STDLIB_PACKAGES: &[&str] = &[
+    "pawn-lang/samp-stdlib",
+    "pawn-lang/pawn-stdlib",
+    "sampctl/samp-stdlib",
+    "sampctl/pawn-stdlib",
+    "openmultiplayer/omp-stdlib",
+];
+
+#[derive(Deserialize)]
+struct CommitRef {
+    sha: SmolStr,
+}
+
+#[derive(Deserialize)]
+struct GitLabRelease {
+    tag_name: String,
+    description: Option<String>,
+    #[serde(default)]
+    upcoming_release: bool,
+    assets: GitLabAssets,
+}
+
+#[derive(Deserialize)]
+struct GitLabAssets {
+    #[serde(default)]
+    links: Vec<GitLabLink>,
+    #[serde(default)]
+    sources: Vec<GitLabArchive>,
+}
+
+#[derive(Deserialize)]
+struct GitLabLink {
+    name: String,
+    url: String,
+    direct_asset_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitLabArchive {
+    format: String,
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaRelease {
+    tag_name: String,
+    body: Option<String>,
+    #[serde(default)]
+    prerelease: bool,
+    tarball_url: Option<String>,
+    #[serde(default)]
+    assets: Vec<GiteaAsset>,
+}
+
+#[derive(Deserialize)]
+struct GiteaAsset {
+    name: String,
+    #[serde(default)]
+    size: u64,
+    browser_download_url: String,
+}
+
+/// Compiles `assets` or `files.exclude` globs; with `anywhere` they match at any depth unless they start with `/`
+fn package_globs(repo: &str, key: &str, patterns: &[SmolStr], anywhere: bool) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in patterns {
+        let trimmed = pattern.trim_start_matches("./");
+        let glob = match trimmed.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if anywhere => format!("**/{}", trimmed),
+            None => trimmed.to_string(),
+        };
+
+        let glob = GlobBuilder::new(&glob)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| {
+                OpenCliError::Config(
+                    format!("Invalid {} pattern {:?} for {}: {}", key, pattern, repo, e).into(),
+                )
+            })?;
+        builder.add(glob);
+    }
+
+    builder.build().map_err(|e| {
+        OpenCliError::Config(format!("Invalid {} patterns for {}: {}", key, repo, e).into())
+    })
+}
+
+/// Token headers from `GITLAB_TOKEN` or `GITEA_TOKEN` for private projects
+fn auth_headers(source: &PackageSource) -> Vec<(String, String)> {
+    let token = |name: &str| std::env::var(name).ok().filter(|token| !token.is_empty());
+
+    match source {
+        PackageSource::GitLab { .. } => token("GITLAB_TOKEN")
+            .map(|token| vec![("PRIVATE-TOKEN".to_string(), token)])
+            .unwrap_or_default(),
+        PackageSource::Gitea { .. } => token("GITEA_TOKEN")
+            .map(|token| vec![("Authorization".to_string(), format!("token {}", token))])
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+async fn run_git(args: &[&str], dir: Option<&Path>, what: &str) -> Result<String> {
+    let mut command = tokio::process::Command::new("git");
+    command.args(args).env("GIT_TERMINAL_PROMPT", "0");
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+
+    let output = command
+        .output()
+        .await
+        .map_err(|e| OpenCliError::Process(format!("git is required to {}: {}", what, e).into()))?;
+
+    if !output.status.success() {
+        return Err(OpenCliError::Process(
+            format!(
+                "Failed to {}: {}",
+                what,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// First 12 characters of a hash over a local file or every file below a directory
+async fn local_version(path: &Path) -> Result<SmolStr> {
+    if !path.exists() {
+        return Err(OpenCliError::NotFound(
+            format!("Package path {} does not exist", path.display()).into(),
+        ));
     }
+
+    let mut hasher = Sha256::new();
+
+    if path.is_dir() {
+        let mut files = Vec::new();
+        let mut pending = vec![path.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let mut entries = tokio::fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let entry_path = entry.path();
+                if entry.file_name() == ".git" {
+                    continue;
+                }
+                if entry.file_type().await?.is_dir() {
+                    pending.push(entry_path);
+                } else {
+                    files.push(entry_path);
+                }
+            }
+        }
+        files.sort();
+
+        for file in files {
+            let relative = file.strip_prefix(path).unwrap_or(&file);
+            hasher.update(relative.to_string_lossy().replace('\\', "/").as_bytes());
+            hasher.update([0]);
+            hasher.update(tokio::fs::read(&file).await?);
+        }
+    } else {
+        hasher.update(tokio::fs::read(path).await?);
+    }
+
+    Ok(format!("{:x}", hasher.finalize())[..12].into())
+}
+
+#[derive(Deserialize)]
+struct DependencyManifest {
+    #[serde(default)]
+    packages: HashMap<SmolStr, PackageSpec>,
+}
+
+fn parse_opencli_dependencies(repo: &str, content: &str) -> Result<Vec<PackageDependency>> {
+    let manifest: DependencyManifest = toml::from_str(content).map_err(|e| {
+        OpenCliError::Config(format!("Invalid opencli.toml in {}: {}", repo, e).into())
+    })?;
+
+    let mut dependencies = Vec::new();
+    for (name, spec) in manifest.packages {
+        let source = spec.source(&name)?;
+
+        dependencies.push(PackageDependency {
+            name,
+            version: spec.version().into(),
+            target: spec.target().cloned(),
+            source,
+        });
+    }
+    dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(dependencies)
+}
+
+/** Parses sampctl dependency strings: `owner/repo`, `owner/repo:tag`, `owner/repo@branch`,
+ * `owner/repo#commit` or a GitHub URL
+ */
+fn parse_pawn_json_dependencies(repo: &str, content: &str) -> Result<Vec<PackageDependency>> {
+    let manifest: serde_json::Value = serde_json::from_str(content).map_err(|e| {
+        OpenCliError::Config(format!("Invalid pawn.json in {}: {}", repo, e).into())
+    })?;
+
+    let mut dependencies = Vec::new();
+
+    let entries = manifest
+        .get("dependencies")
+        .and_then(|d| d.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|d| d.as_str());
+
+    for entry in entries {
+        let entry = entry
+            .trim()
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_start_matches("github.com/");
+
+        let (name, version, branch, rev) = if let Some((name, branch)) = entry.split_once('@') {
+            (name, "*", Some(branch), None)
+        } else if let Some((name, rev)) = entry.split_once('#') {
+            (name, "*", None, Some(rev))
+        } else if let Some((name, tag)) = entry.split_once(':') {
+            (name, tag, None, None)
+        } else {
+            (entry, "*", None, None)
+        };
+        let name = name.trim_end_matches(".git");
+
+        if !GITHUB_REPO_REGEX.is_match(name) {
+            log::warn!("Skipping unrecognized dependency {} of {}", entry, repo);
+            continue;
+        }
+
+        if STDLIB_PACKAGES
+            .iter()
+            .any(|stdlib| stdlib.eq_ignore_ascii_case(name))
+        {
+            continue;
+        }
+
+        let source = (branch.is_some() || rev.is_some()).then(|| {
+            PackageSource::Git(GitSource {
+                git: name.into(),
+                rev: rev.map(SmolStr::from),
+                branch: branch.map(SmolStr::from),
+                tag: None,
+            })
+        });
+
+        dependencies.push(PackageDependency {
+            name: name.into(),
+            version: version.into(),
+            target: None,
+            source,
+        });
+    }
+
+    dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+    dependencies.dedup_by(|a, b| a.name == b.name);
+
+    Ok(dependencies)
 }
@@ -1,5 +1,7 @@
 use crate::build::PackageTarget;
+use crate::package::source::PackageSource;
 use crate::result::Result;
+use crate::utils::platform::Platform;
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 use std::collections::HashMap;
@@ -9,14 +11,88 @@ use tokio::fs;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstalledPackage {
     pub version: SmolStr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraint: Option<SmolStr>,
     pub target: Option<PackageTarget>,
+    /// Platforms whose assets were installed; empty for entries locked before they were recorded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub platforms: Vec<Platform>,
+    /// `assets` globs from opencli.toml the release assets were picked with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub asset_patterns: Vec<SmolStr>,
+    /// `files.exclude` globs from opencli.toml
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_files: Vec<SmolStr>,
     pub hash: SmolStr,
     pub installed_at: SmolStr,
     pub files: Vec<SmolStr>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assets: Vec<LockedAsset>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub file_sources: Vec<LockedFile>,
+    /// Requirements declared by this release; `None` for entries locked before they were recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<Vec<PackageDependency>>,
+    /// Where the package came from when not a GitHub release; pinned sources lock their
+    /// commit or content hash as `version`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PackageSource>,
+    /// Local path from `[patch]` the files were copied from instead of `source`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<SmolStr>,
+    /// Files from `patches/` applied on top of the installed files, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patches: Vec<AppliedPatch>,
+}
+
+/// A package required by another package, from its opencli.toml or pawn.json
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageDependency {
+    pub name: SmolStr,
+    pub version: SmolStr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<PackageTarget>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PackageSource>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedAsset {
+    pub name: SmolStr,
+    pub url: SmolStr,
+    pub sha256: SmolStr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedFile {
+    pub source: SmolStr,
+    pub destination: SmolStr,
+    pub sha256: SmolStr,
+    /// Hash of the installed file after the project's patches, when any changed it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patched_sha256: Option<SmolStr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedPatch {
+    pub file: SmolStr,
+    pub sha256: SmolStr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedCompiler {
+    pub constraint: SmolStr,
+    pub version: SmolStr,
+    /// Repository the release came from, e.g. `openmultiplayer/compiler`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SmolStr>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct PackageLock {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compiler: Option<LockedCompiler>,
+    #[serde(default)]
     pub installed: HashMap<SmolStr, InstalledPackage>,
 }
 
@@ -52,14 +128,28 @@ impl PackageLock {
         let installed_at = chrono::Utc::now().to_rfc3339().into();
         let package = InstalledPackage {
             version,
+            constraint: None,
             target,
+            platforms: Vec::new(),
+            asset_patterns: Vec::new(),
+            excluded_files: Vec::new(),
             hash,
             installed_at,
             files,
+            assets: Vec::new(),
+            file_sources: Vec::new(),
+            dependencies: None,
+            source: None,
+            patch: None,
+            patches: Vec::new(),
         };
         self.installed.insert(name, package);
     }
 
+    pub fn insert_package(&mut self, name: SmolStr, package: InstalledPackage) {
+        self.installed.insert(name, package);
+    }
+
     pub fn remove_package(&mut self, name: &str) -> Option<InstalledPackage> {
         self.installed.remove(name)
     }
@@ -76,6 +166,20 @@ impl PackageLock {
         self.installed.get(name).map(|p| p.version.as_str())
     }
 
+    pub fn set_compiler(&mut self, constraint: SmolStr, version: SmolStr, source: Option<SmolStr>) {
+        self.compiler = Some(LockedCompiler {
+            constraint,
+            version,
+            source,
+        });
+    }
+
+    pub fn get_locked_compiler(&self, constraint: &str) -> Option<&LockedCompiler> {
+        self.compiler
+            .as_ref()
+            .filter(|c| c.constraint == constraint)
+    }
+
     pub fn list_packages(&self) -> Vec<(&str, &InstalledPackage)> {
         self.installed
             .iter()
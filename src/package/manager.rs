@@ -1,15 +1,31 @@
 use crate::build::{BuildConfig, PackageSpec, PackageTarget};
 use crate::cache::CacheManager;
+use crate::package::downloader::{GitHubRelease, PackageFiles, PackageSelection};
+use crate::package::source::display_version;
+use crate::package::version::Version;
 use crate::package::{
-    ConfigManager, PackageDownloader, PackageLock, VersionConstraint, WorkspaceDetector,
+    AppliedPatch, ConfigManager, DependencyResolver, InstalledPackage, LockedFile,
+    PackageDependency, PackageDownloader, PackageLock, PackageSource, ResolvedPackage,
+    VersionConstraint, WorkspaceDetector,
 };
 use crate::result::{OpenCliError, Result};
 use crate::security::SecurityManager;
-use indicatif::{ProgressBar, ProgressStyle};
+use crate::utils::diff;
+use crate::utils::platform::{platform_list, Platform};
+use futures::stream::{self, StreamExt};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::Serialize;
 use smol_str::SmolStr;
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
+/// Project folder with `<owner>__<repo>/*.patch` files applied after installing a package
+const PATCHES_DIR: &str = "patches";
+
+/// Numbers package download folders so parallel runs and similar names never share one
+static TEMP_COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
 pub struct PackageManager {
     downloader: PackageDownloader,
     workspace: WorkspaceDetector,
@@ -18,6 +34,8 @@ pub struct PackageManager {
     config_manager: ConfigManager,
     config_path: PathBuf,
     lock_path: PathBuf,
+    /// `--platform` override for every package
+    platforms: Option<Vec<Platform>>,
 }
 
 impl PackageManager {
@@ -34,7 +52,28 @@ impl PackageManager {
             config_manager: ConfigManager::new(workspace_path),
             config_path: config_path_buf,
             lock_path,
+            platforms: None,
+        }
+    }
+
+    /// Installs assets for `platforms` instead of each package's own or this machine's
+    pub fn set_platforms(&mut self, platforms: Vec<Platform>) {
+        self.platforms = Some(platforms);
+    }
+
+    /// `--platform`, else the package's `platforms` in opencli.toml, else this machine
+    fn platforms_for(&self, spec: Option<&PackageSpec>) -> Vec<Platform> {
+        if let Some(platforms) = &self.platforms {
+            return platforms.clone();
         }
+
+        let mut platforms = match spec.and_then(PackageSpec::platforms) {
+            Some(platforms) => platforms.to_vec(),
+            None => Platform::current().into_iter().collect(),
+        };
+        platforms.sort();
+        platforms.dedup();
+        platforms
     }
 
     pub async fn install_package(
@@ -61,92 +100,1517 @@ impl PackageManager {
             return Ok(());
         }
 
-        let constraint = if let Some(spec) = version_spec {
-            VersionConstraint::parse(spec)?
+        let mut config =
+            BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+
+        spinner.set_message("Resolving dependencies...");
+        let requested = PackageDependency {
+            name: repo.into(),
+            version: version_spec.unwrap_or("*").into(),
+            target: target.clone(),
+            source: None,
+        };
+        let resolved = self
+            .resolve_packages(&config, &lock, Some(requested), None)
+            .await?;
+
+        // Download everything first so a failed dependency leaves the workspace untouched
+        let mut prepared = Vec::new();
+        for (name, package) in &resolved {
+            if lock.is_package_installed(name) {
+                continue;
+            }
+
+            match self.prepare_resolved(name, package, &spinner).await {
+                Ok(package) => prepared.push(package),
+                Err(e) => {
+                    for package in &prepared {
+                        self.cleanup_temp_dir(&package.temp_dir).await?;
+                    }
+                    spinner.finish_and_clear();
+                    return Err(e);
+                }
+            }
+        }
+
+        let tag_name = resolved[repo].version.clone();
+
+        // Without an explicit constraint the project is pinned to the tag that was installed
+        let constraint: SmolStr = version_spec.unwrap_or(&tag_name).into();
+
+        spinner.set_message("Installing package files...");
+        let mut prepared = prepared.into_iter();
+        while let Some(mut package) = prepared.next() {
+            if package.repo == repo {
+                package.constraint = constraint.clone();
+            } else {
+                println!(
+                    "Installing dependency {} {}",
+                    package.repo,
+                    display_version(&package.release.tag_name)
+                );
+            }
+            if let Err(e) = self.apply_package(package, &mut lock).await {
+                for package in prepared {
+                    self.cleanup_temp_dir(&package.temp_dir).await?;
+                }
+                return Err(e);
+            }
+        }
+        Self::record_dependencies(&mut lock, &resolved);
+
+        let spec = if let Some(target) = target {
+            PackageSpec::new_detailed(constraint, Some(target))
         } else {
-            VersionConstraint::parse("*")?
+            PackageSpec::new_simple(constraint)
         };
+        config.add_package(repo.into(), spec);
 
-        spinner.set_message(format!("Finding version for {}", repo));
-        let release = self
-            .downloader
-            .find_matching_version(repo, &constraint)
+        spinner.set_message("Updating lock file...");
+        lock.save_to_file(&self.lock_path).await?;
+
+        spinner.set_message("Updating configuration...");
+        config
+            .save_to_file(self.config_path.to_string_lossy().as_ref())
+            .await?;
+
+        spinner.set_message("Updating config.json...");
+        self.config_manager
+            .update_legacy_plugins(&self.lock_path)
             .await?;
 
-        spinner.set_message("Downloading package files...");
+        spinner.finish_with_message(format!("Successfully installed {} {}", repo, tag_name));
+        log::info!("Package installed: {} {}", repo, tag_name);
+
+        Ok(())
+    }
+
+    pub async fn install_all_packages(&mut self, jobs: usize) -> Result<()> {
+        let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+        let mut lock = PackageLock::load_from_file(&self.lock_path).await?;
+
+        if config
+            .get_packages()
+            .is_none_or(|packages| packages.is_empty())
+        {
+            println!("No packages defined in configuration");
+            return Ok(());
+        }
+
+        let spinner = self.create_spinner("Resolving dependencies...");
+        let resolved = self.resolve_packages(&config, &lock, None, None).await;
+        spinner.finish_and_clear();
+        let resolved = resolved?;
+
+        let mut pending: Vec<(&SmolStr, &ResolvedPackage)> = Vec::new();
+        for (repo, package) in &resolved {
+            if let Some(installed_version) = lock.get_installed_version(repo) {
+                println!(
+                    "Package {} {} is already installed",
+                    repo, installed_version
+                );
+            } else {
+                pending.push((repo, package));
+            }
+        }
+
+        if pending.is_empty() {
+            if Self::record_dependencies(&mut lock, &resolved) {
+                lock.save_to_file(&self.lock_path).await?;
+            }
+            return Ok(());
+        }
+
+        let jobs = jobs.max(1);
+        println!(
+            "Installing {} package(s) with up to {} parallel job(s)",
+            pending.len(),
+            jobs
+        );
+
+        // Resolve and download concurrently, nothing in the workspace is touched yet
+        let multi = MultiProgress::new();
+        let this: &Self = self;
+        let mut prepared: Vec<(SmolStr, Result<PreparedPackage>)> = stream::iter(&pending)
+            .map(|(repo, package)| {
+                let pb = multi.add(ProgressBar::new_spinner());
+                pb.set_style(Self::spinner_style());
+                pb.set_message(format!("{}: waiting", repo));
+                pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+                async move {
+                    let result = this.prepare_resolved(repo, package, &pb).await;
+
+                    match &result {
+                        Ok(package) => pb.finish_with_message(format!(
+                            "{}: {} ready",
+                            repo,
+                            display_version(&package.release.tag_name)
+                        )),
+                        Err(_) => pb.abandon_with_message(format!("{}: failed", repo)),
+                    }
+
+                    ((*repo).clone(), result)
+                }
+            })
+            .buffer_unordered(jobs)
+            .collect()
+            .await;
+
+        // Apply in a fixed order so the workspace and lock end up identical across runs
+        prepared.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut installed = 0;
+        let mut failed = 0;
+
+        for (repo, result) in prepared {
+            let result = match result {
+                Ok(package) => {
+                    let tag_name = display_version(&package.release.tag_name).to_string();
+                    self.apply_package(package, &mut lock)
+                        .await
+                        .map(|_| tag_name)
+                }
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(tag_name) => {
+                    installed += 1;
+                    println!("Installed {} {}", repo, tag_name);
+                    log::info!("Package installed: {} {}", repo, tag_name);
+                }
+                Err(e) => {
+                    failed += 1;
+                    eprintln!("Failed to install {}: {}", repo, e);
+                    log::error!("Package installation failed: {} - {}", repo, e);
+                }
+            }
+        }
+
+        if Self::record_dependencies(&mut lock, &resolved) || installed > 0 {
+            lock.save_to_file(&self.lock_path).await?;
+        }
+
+        if installed > 0 {
+            self.config_manager
+                .update_legacy_plugins(&self.lock_path)
+                .await?;
+        }
+
+        println!("{} installed, {} failed", installed, failed);
+
+        Ok(())
+    }
+
+    async fn prepare_release(
+        &self,
+        repo: &str,
+        release: GitHubRelease,
+        constraint: &str,
+        selection: PackageSelection,
+        source: Option<PackageSource>,
+        progress: &ProgressBar,
+    ) -> Result<PreparedPackage> {
+        progress.set_message(format!(
+            "Downloading {} {}",
+            repo,
+            display_version(&release.tag_name)
+        ));
         let temp_dir = self.get_temp_dir(repo)?;
-        let package_files = self
+        let files = self
             .downloader
-            .download_package(repo, &release, &temp_dir, target.as_ref())
+            .download_package(
+                repo,
+                source.as_ref(),
+                &release,
+                &temp_dir,
+                &selection,
+                Some(progress),
+            )
+            .await;
+        let files = self.cleanup_on_error(&temp_dir, files).await?;
+
+        progress.set_style(Self::spinner_style());
+
+        if let Some(min_compiler) = &files.min_compiler {
+            if let Err(e) = self.check_min_compiler(repo, min_compiler).await {
+                self.cleanup_temp_dir(&temp_dir).await?;
+                return Err(e);
+            }
+        }
+
+        Ok(PreparedPackage {
+            repo: repo.into(),
+            constraint: constraint.into(),
+            release,
+            selection,
+            files,
+            temp_dir,
+            direct: true,
+            dependencies: None,
+            source,
+            patch: None,
+        })
+    }
+
+    async fn check_min_compiler(&self, repo: &str, min_compiler: &str) -> Result<()> {
+        let required = Version::parse(min_compiler).map_err(|_| {
+            OpenCliError::Config(
+                format!(
+                    "{} declares an invalid min_compiler: {}",
+                    repo, min_compiler
+                )
+                .into(),
+            )
+        })?;
+
+        let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+        let project = &config.build.compiler_version;
+
+        // A constraint is checked against the compiler it resolved to, or
+        // failing that the oldest version it allows
+        let configured = match Version::parse(project) {
+            Ok(version) => version,
+            Err(_) => {
+                let lock = PackageLock::load_from_file(&self.lock_path).await?;
+                let locked = lock
+                    .get_locked_compiler(project)
+                    .and_then(|c| Version::parse(&c.version).ok());
+
+                match locked.or_else(|| Self::lower_bound(project)) {
+                    Some(version) => version,
+                    None => {
+                        return Err(OpenCliError::Config(
+                            format!(
+                                "{} requires compiler {} or newer, but the compiler for '{}' is not known yet; run `opencli build` or `opencli compiler update` first",
+                                repo, min_compiler, project
+                            )
+                            .into(),
+                        ))
+                    }
+                }
+            }
+        };
+
+        if configured < required {
+            return Err(OpenCliError::Config(
+                format!(
+                    "{} requires compiler {} or newer, but opencli.toml uses {}",
+                    repo, min_compiler, configured
+                )
+                .into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Oldest version a `compiler_version` constraint allows, if it has one
+    fn lower_bound(constraint: &str) -> Option<Version> {
+        if matches!(constraint.trim(), "*" | "latest") {
+            return None;
+        }
+
+        match VersionConstraint::parse(constraint).ok()? {
+            VersionConstraint::Exact(v)
+            | VersionConstraint::Caret(v)
+            | VersionConstraint::Tilde(v)
+            | VersionConstraint::GreaterThan(v)
+            | VersionConstraint::GreaterEqual(v)
+            | VersionConstraint::Range(v, _) => Some(v),
+            VersionConstraint::LessThan(_) | VersionConstraint::LessEqual(_) => None,
+        }
+    }
+
+    async fn prepare_resolved(
+        &self,
+        repo: &str,
+        resolved: &ResolvedPackage,
+        progress: &ProgressBar,
+    ) -> Result<PreparedPackage> {
+        let origin = match &resolved.patch {
+            Some(path) => Some(PackageSource::Path { path: path.clone() }),
+            None => resolved.source.clone(),
+        };
+
+        let release = match &resolved.release {
+            Some(release) => release.clone(),
+            None => {
+                progress.set_message(format!(
+                    "Finding {} {}",
+                    repo,
+                    display_version(&resolved.version)
+                ));
+                self.downloader
+                    .release_for(repo, origin.as_ref(), &resolved.version)
+                    .await?
+            }
+        };
+
+        let mut prepared = self
+            .prepare_release(
+                repo,
+                release,
+                &resolved.constraint,
+                PackageSelection {
+                    target: resolved.target.clone(),
+                    platforms: resolved.platforms.clone(),
+                    asset_patterns: resolved.asset_patterns.clone(),
+                    excluded_files: resolved.excluded_files.clone(),
+                },
+                origin,
+                progress,
+            )
             .await?;
+        prepared.direct = resolved.direct;
+        prepared.dependencies = Some(resolved.dependencies.clone());
+        prepared.source = resolved.source.clone();
+        prepared.patch = resolved.patch.clone();
 
-        spinner.set_message("Installing package files...");
-        let installed_files = self
-            .install_package_files(repo, &package_files, target.as_ref())
+        Ok(prepared)
+    }
+
+    /** Resolves opencli.toml `[packages]` and their dependencies
+     *
+     * # Arguments
+     * * `extra` - Additional root, e.g. a package being installed from the command line
+     * * `unlock` - Packages to move off their locked version (`None` keeps every lock)
+     */
+    async fn resolve_packages(
+        &self,
+        config: &BuildConfig,
+        lock: &PackageLock,
+        extra: Option<PackageDependency>,
+        unlock: Option<Option<&str>>,
+    ) -> Result<BTreeMap<SmolStr, ResolvedPackage>> {
+        let mut roots = Vec::new();
+        for (name, spec) in config.get_packages().into_iter().flatten() {
+            let source = spec.source(name)?;
+
+            roots.push(PackageDependency {
+                name: name.clone(),
+                version: spec.version().into(),
+                target: spec.target().cloned(),
+                source,
+            });
+        }
+        if let Some(extra) = extra {
+            roots.retain(|root| root.name != extra.name);
+            roots.push(extra);
+        }
+        roots.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let patches = config.get_patches()?;
+
+        let mut resolver = DependencyResolver::new(&self.downloader, lock);
+        match unlock {
+            Some(Some(repo)) => resolver.unlock(repo),
+            Some(None) => resolver.unlock_all(),
+            None => {}
+        }
+        for (repo, path) in &patches {
+            resolver.patch(repo, path);
+        }
+
+        let mut resolved = resolver.resolve(&roots).await?;
+        for (repo, package) in resolved.iter_mut() {
+            let spec = config
+                .get_packages()
+                .and_then(|packages| packages.get(repo));
+            package.platforms = self.platforms_for(spec);
+            if let Some(spec) = spec {
+                package.asset_patterns = spec.asset_patterns().to_vec();
+                package.excluded_files = spec.excluded_files().to_vec();
+            }
+        }
+
+        for (repo, path) in &patches {
+            if !resolved.contains_key(repo) {
+                log::warn!(
+                    "[patch] {} = {} is unused, no package depends on {}",
+                    repo,
+                    path,
+                    repo
+                );
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Stores each package's resolved dependencies in the lock; true if anything changed
+    fn record_dependencies(
+        lock: &mut PackageLock,
+        resolved: &BTreeMap<SmolStr, ResolvedPackage>,
+    ) -> bool {
+        let mut changed = false;
+
+        for (repo, package) in resolved {
+            let Some(entry) = lock.installed.get_mut(repo) else {
+                continue;
+            };
+            if entry.version != package.version {
+                continue;
+            }
+
+            if entry.dependencies.as_ref() != Some(&package.dependencies) {
+                entry.dependencies = Some(package.dependencies.clone());
+                changed = true;
+            }
+            if !package.direct && entry.constraint.is_some() {
+                entry.constraint = None;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Installs a downloaded package and records it in the lock, then removes its download
+    async fn apply_package(&self, package: PreparedPackage, lock: &mut PackageLock) -> Result<()> {
+        let temp_dir = package.temp_dir.clone();
+        let result = self.install_prepared(package, lock).await;
+        let cleanup = self.cleanup_temp_dir(&temp_dir).await;
+        result.and(cleanup)
+    }
+
+    async fn install_prepared(
+        &self,
+        package: PreparedPackage,
+        lock: &mut PackageLock,
+    ) -> Result<()> {
+        let repo = package.repo.as_str();
+        let copied = self
+            .install_package_files(
+                &package.files,
+                &package.temp_dir,
+                package.selection.target.as_ref(),
+            )
             .await?;
+        let installed_files: Vec<PathBuf> = copied.iter().map(|(_, dest)| dest.clone()).collect();
+
+        let mut file_sources = Vec::new();
+        for (source, destination) in &copied {
+            file_sources.push(LockedFile {
+                source: relative_slash_path(source, &package.temp_dir).into(),
+                destination: self.workspace_relative(destination).into(),
+                sha256: self.security.sha256_file(destination).await?.into(),
+                patched_sha256: None,
+            });
+        }
+
+        let patches = match self
+            .apply_patches(repo, &package.release.tag_name, &mut file_sources)
+            .await
+        {
+            Ok(patches) => patches,
+            Err(e) => {
+                // Nothing is locked yet, so don't leave unpatched files behind
+                for file in &installed_files {
+                    let _ = fs::remove_file(file).await;
+                }
+                return Err(e);
+            }
+        };
 
-        spinner.set_message("Computing package hash...");
         let combined_hash = self.compute_package_hash(&installed_files).await?;
         println!("Package hash (Argon2): {}", combined_hash);
         log::info!("Package {} hash: {}", repo, combined_hash);
 
-        spinner.set_message("Updating cache...");
         for file_path in &installed_files {
             if let Some(file_name) = file_path.file_name().and_then(|n| n.to_str()) {
                 self.cache.store_hash(file_name, &combined_hash).await?;
             }
         }
 
-        spinner.set_message("Updating lock file...");
         let file_names: Vec<SmolStr> = installed_files
             .iter()
             .filter_map(|p| p.to_str().map(|s| s.into()))
             .collect();
 
-        lock.add_package(
-            repo.into(),
-            release.tag_name.clone().into(),
-            target.clone(),
-            combined_hash.into(),
-            file_names,
+        lock.insert_package(
+            package.repo.clone(),
+            InstalledPackage {
+                version: package.release.tag_name.as_str().into(),
+                constraint: (package.direct
+                    && package
+                        .source
+                        .as_ref()
+                        .is_none_or(PackageSource::is_versioned))
+                .then_some(package.constraint),
+                target: package.selection.target,
+                platforms: package.selection.platforms,
+                asset_patterns: package.selection.asset_patterns,
+                excluded_files: package.selection.excluded_files,
+                hash: combined_hash.into(),
+                installed_at: chrono::Utc::now().to_rfc3339().into(),
+                files: file_names,
+                assets: package.files.assets,
+                file_sources,
+                dependencies: package.dependencies,
+                source: package.source,
+                patch: package.patch,
+                patches,
+            },
         );
-        lock.save_to_file(&self.lock_path).await?;
 
-        spinner.set_message("Updating configuration...");
-        self.update_config(repo, &release.tag_name, target).await?;
+        Ok(())
+    }
+
+    /// The project's `patches/<owner>__<repo>/*.patch` files for `repo`, in file name order
+    async fn project_patches(&self, repo: &str) -> Result<Vec<(PathBuf, AppliedPatch)>> {
+        let dir = self
+            .workspace_path(PATCHES_DIR)
+            .join(repo.replace('/', "__"));
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut paths = Vec::new();
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "patch") && path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut patches = Vec::new();
+        for path in paths {
+            let applied = AppliedPatch {
+                file: self.workspace_relative(&path).into(),
+                sha256: self.security.sha256_file(&path).await?.into(),
+            };
+            patches.push((path, applied));
+        }
+
+        Ok(patches)
+    }
+
+    /// Whether `patches/` still holds exactly the patches locked for `package`
+    async fn patches_match(&self, repo: &str, package: &InstalledPackage) -> Result<bool> {
+        let patches: Vec<AppliedPatch> = self
+            .project_patches(repo)
+            .await?
+            .into_iter()
+            .map(|(_, applied)| applied)
+            .collect();
+
+        Ok(patches == package.patches)
+    }
+
+    /** Applies the project's patch files for `repo` to its installed files
+     *
+     * Every patch is checked before anything is written, so a patch that no
+     * longer applies leaves the installed files as they were. Changed files get
+     * their new hash in `patched_sha256`.
+     */
+    async fn apply_patches(
+        &self,
+        repo: &str,
+        version: &str,
+        files: &mut [LockedFile],
+    ) -> Result<Vec<AppliedPatch>> {
+        let patches = self.project_patches(repo).await?;
+        let mut contents: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+
+        for (path, applied) in &patches {
+            let diffs = diff::parse(&fs::read(path).await?).map_err(|e| {
+                OpenCliError::Config(format!("Invalid patch {}: {}", applied.file, e).into())
+            })?;
+
+            for file_diff in diffs {
+                let index = patched_file(files, &file_diff.path).ok_or_else(|| {
+                    OpenCliError::Config(
+                        format!(
+                            "{} changes {}, which is not a file of {}",
+                            applied.file, file_diff.path, repo
+                        )
+                        .into(),
+                    )
+                })?;
+
+                let current = match contents.remove(&index) {
+                    Some(content) => content,
+                    None => fs::read(self.workspace_path(&files[index].destination)).await?,
+                };
+                let patched = diff::apply(&current, &file_diff).map_err(|e| {
+                    OpenCliError::Process(
+                        format!(
+                            "Patch {} no longer applies to {} in {} {}: {}; update or remove the patch",
+                            applied.file,
+                            files[index].destination,
+                            repo,
+                            display_version(version),
+                            e
+                        )
+                        .into(),
+                    )
+                })?;
+                contents.insert(index, patched);
+            }
+        }
+
+        for (index, content) in contents {
+            let destination = self.workspace_path(&files[index].destination);
+            fs::write(&destination, content).await?;
+            files[index].patched_sha256 =
+                Some(self.security.sha256_file(&destination).await?.into());
+        }
+
+        for (_, applied) in &patches {
+            println!("Applied {} to {}", applied.file, repo);
+        }
+
+        Ok(patches.into_iter().map(|(_, applied)| applied).collect())
+    }
+
+    /** Installs exactly the packages, assets and files recorded in opencli.lock
+     *
+     * # Arguments
+     * * `frozen` - Also fail when opencli.toml and opencli.lock disagree
+     * * `jobs` - Maximum number of packages downloaded in parallel
+     *
+     * # Notes
+     * - Never queries the GitHub API; assets are fetched from their locked URLs
+     *   (or the package cache) and must match their locked SHA-256
+     * - The lock file itself is never rewritten
+     */
+    pub async fn install_locked(&mut self, frozen: bool, jobs: usize) -> Result<()> {
+        let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+        let lock = PackageLock::load_from_file(&self.lock_path).await?;
+        let flag = if frozen { "--frozen" } else { "--locked" };
+
+        let mut problems = Vec::new();
+        let empty = Default::default();
+        let packages = config.get_packages().unwrap_or(&empty);
+
+        for (repo, spec) in packages {
+            match lock.get_package(repo) {
+                None => problems.push(format!(
+                    "{} is in opencli.toml but not in opencli.lock",
+                    repo
+                )),
+                Some(locked) if frozen => {
+                    let source = spec.source(repo)?;
+                    if locked.source != source {
+                        problems.push(format!(
+                            "{} has a different source in opencli.toml and opencli.lock",
+                            repo
+                        ));
+                    } else if source.as_ref().is_none_or(PackageSource::is_versioned)
+                        && locked.constraint.as_deref() != Some(spec.version())
+                    {
+                        problems.push(format!(
+                            "{} is \"{}\" in opencli.toml but was locked for \"{}\"",
+                            repo,
+                            spec.version(),
+                            locked.constraint.as_deref().unwrap_or("unknown")
+                        ));
+                    }
+                    if locked.target.as_ref() != spec.target() {
+                        problems.push(format!(
+                            "{} has a different target in opencli.toml and opencli.lock",
+                            repo
+                        ));
+                    }
+                    if locked.asset_patterns != spec.asset_patterns()
+                        || locked.excluded_files != spec.excluded_files()
+                    {
+                        problems.push(format!(
+                            "{} has different assets or files.exclude in opencli.toml and opencli.lock",
+                            repo
+                        ));
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+
+        // CI must build what the lock describes, not someone's local checkout
+        for (repo, path) in config.get_patches()? {
+            problems.push(format!(
+                "{} is patched to {} in opencli.toml; remove the [patch] entry",
+                repo, path
+            ));
+        }
+        for (repo, package) in lock.list_packages() {
+            if let Some(path) = &package.patch {
+                problems.push(format!(
+                    "{} was locked from the local patch {}; run 'opencli package sync' without it",
+                    repo, path
+                ));
+            }
+        }
+
+        for (repo, package) in lock.list_packages() {
+            if !self.patches_match(repo, package).await? {
+                problems.push(format!(
+                    "{} has different files in patches/ than opencli.lock records; run 'opencli package sync'",
+                    repo
+                ));
+            }
+        }
+
+        // Packages pulled in by another locked package are expected in the lock
+        let required: HashSet<&str> = lock
+            .list_packages()
+            .into_iter()
+            .flat_map(|(_, package)| package.dependencies.iter().flatten())
+            .map(|dependency| dependency.name.as_str())
+            .collect();
+
+        if frozen {
+            for (repo, _) in lock.list_packages() {
+                if !packages.contains_key(repo) && !required.contains(repo) {
+                    problems.push(format!(
+                        "{} is in opencli.lock but not in opencli.toml",
+                        repo
+                    ));
+                }
+            }
+        }
+
+        for (repo, package) in lock.list_packages() {
+            for dependency in package.dependencies.iter().flatten() {
+                if !lock.is_package_installed(&dependency.name) {
+                    problems.push(format!(
+                        "{} depends on {} which is not in opencli.lock",
+                        repo, dependency.name
+                    ));
+                }
+            }
+        }
+
+        for (repo, package) in lock.list_packages() {
+            let platforms = self.platforms_for(packages.get(repo));
+            if !package.platforms.is_empty() && platforms_changed(package, &platforms) {
+                let flag = package
+                    .platforms
+                    .iter()
+                    .map(Platform::as_str)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                problems.push(format!(
+                    "{} was locked for {} but is being installed for {}; pass --platform {} or run 'opencli package sync'",
+                    repo,
+                    platform_list(&package.platforms),
+                    platform_list(&platforms),
+                    flag
+                ));
+            }
+        }
+
+        for (repo, package) in lock.list_packages() {
+            if package.assets.is_empty()
+                && !package.files.is_empty()
+                && package.patch.is_none()
+                && package
+                    .source
+                    .as_ref()
+                    .is_none_or(PackageSource::has_assets)
+            {
+                problems.push(format!(
+                    "{} was locked by an older opencli without asset hashes; run 'opencli package install' once to refresh opencli.lock",
+                    repo
+                ));
+            }
+        }
+
+        if !problems.is_empty() {
+            problems.sort();
+            return Err(OpenCliError::Config(
+                format!(
+                    "Cannot install with {}:\n  - {}",
+                    flag,
+                    problems.join("\n  - ")
+                )
+                .into(),
+            ));
+        }
+
+        let mut pending = Vec::new();
+        for (repo, package) in lock.list_packages() {
+            if self.locked_files_intact(package).await {
+                println!(
+                    "Package {} {} is up to date",
+                    repo,
+                    display_version(&package.version)
+                );
+            } else {
+                pending.push((SmolStr::from(repo), package.clone()));
+            }
+        }
+        pending.sort_by(|a, b| a.0.cmp(&b.0));
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let jobs = jobs.max(1);
+        println!(
+            "Installing {} locked package(s) with up to {} parallel job(s)",
+            pending.len(),
+            jobs
+        );
+
+        // Fail before downloading so no download folder is left behind
+        self.workspace.ensure_workspace_structure().await?;
+
+        let multi = MultiProgress::new();
+        let this: &Self = self;
+        let mut prepared: Vec<(SmolStr, Result<PathBuf>)> = stream::iter(&pending)
+            .map(|(repo, package)| {
+                let pb = multi.add(ProgressBar::new_spinner());
+                pb.set_style(Self::spinner_style());
+                pb.set_message(format!("{}: waiting", repo));
+                pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+                async move {
+                    let result = match this.get_temp_dir(repo) {
+                        Ok(temp_dir) => {
+                            let downloaded = this
+                                .downloader
+                                .download_locked_assets(
+                                    repo,
+                                    package.source.as_ref(),
+                                    &package.version,
+                                    &package.assets,
+                                    &temp_dir,
+                                    Some(&pb),
+                                )
+                                .await;
+                            this.cleanup_on_error(&temp_dir, downloaded)
+                                .await
+                                .map(|_| temp_dir)
+                        }
+                        Err(e) => Err(e),
+                    };
+
+                    pb.set_style(Self::spinner_style());
+                    match &result {
+                        Ok(_) => pb.finish_with_message(format!(
+                            "{}: {} ready",
+                            repo,
+                            display_version(&package.version)
+                        )),
+                        Err(_) => pb.abandon_with_message(format!("{}: failed", repo)),
+                    }
+
+                    (repo.clone(), result)
+                }
+            })
+            .buffer_unordered(jobs)
+            .collect()
+            .await;
+        prepared.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut failures = Vec::new();
+        for ((repo, package), (_, result)) in pending.iter().zip(prepared) {
+            let result = match result {
+                Ok(temp_dir) => {
+                    let applied = self.apply_locked_files(repo, package, &temp_dir).await;
+                    self.cleanup_temp_dir(&temp_dir).await?;
+                    applied.map(|_| ())
+                }
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(()) => {
+                    println!(
+                        "Installed {} {} (locked)",
+                        repo,
+                        display_version(&package.version)
+                    );
+                    log::info!("Locked package installed: {} {}", repo, package.version);
+                }
+                Err(e) => {
+                    eprintln!("Failed to install {}: {}", repo, e);
+                    log::error!("Locked package installation failed: {} - {}", repo, e);
+                    failures.push(repo.to_string());
+                }
+            }
+        }
 
-        spinner.set_message("Updating config.json...");
         self.config_manager
             .update_legacy_plugins(&self.lock_path)
             .await?;
 
-        self.cleanup_temp_dir(&temp_dir).await?;
+        if !failures.is_empty() {
+            return Err(OpenCliError::Process(
+                format!(
+                    "Failed to reproduce locked packages: {}",
+                    failures.join(", ")
+                )
+                .into(),
+            ));
+        }
+
+        Ok(())
+    }
 
-        spinner.finish_with_message(format!(
-            "Successfully installed {} {}",
-            repo, release.tag_name
-        ));
-        log::info!("Package installed: {} {}", repo, release.tag_name);
+    async fn locked_files_intact(&self, package: &InstalledPackage) -> bool {
+        if package.file_sources.is_empty() {
+            return false;
+        }
+
+        for file in &package.file_sources {
+            let destination = self.workspace_path(&file.destination);
+
+            let expected = file.patched_sha256.as_ref().unwrap_or(&file.sha256);
+            match self.security.sha256_file(&destination).await {
+                Ok(actual) if SecurityManager::digest_matches(expected, &actual) => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+
+    /// Copies the locked files of `package` and applies the project's patches again
+    async fn apply_locked_files(
+        &self,
+        repo: &str,
+        package: &InstalledPackage,
+        temp_dir: &Path,
+    ) -> Result<(Vec<LockedFile>, Vec<AppliedPatch>)> {
+        let root = self.workspace.get_workspace_info().root;
+        let mut copies = Vec::new();
+
+        // Verify every file before copying so a bad asset never half-installs a package
+        for file in &package.file_sources {
+            let source = safe_join(temp_dir, &file.source).ok_or_else(|| {
+                OpenCliError::Config(
+                    format!("Invalid source path in opencli.lock: {}", file.source).into(),
+                )
+            })?;
+            // A crafted or badly merged lock must not write outside the project
+            let destination = safe_join(&root, &file.destination).ok_or_else(|| {
+                OpenCliError::Config(
+                    format!(
+                        "Invalid destination path in opencli.lock: {}",
+                        file.destination
+                    )
+                    .into(),
+                )
+            })?;
+
+            if !source.exists() {
+                return Err(OpenCliError::NotFound(
+                    format!(
+                        "{} {} no longer provides {}",
+                        repo, package.version, file.source
+                    )
+                    .into(),
+                ));
+            }
+
+            let actual = self.security.sha256_file(&source).await?;
+            if !SecurityManager::digest_matches(&file.sha256, &actual) {
+                return Err(OpenCliError::Process(
+                    format!(
+                        "{} in {} {} does not match opencli.lock: expected sha256 {}, got sha256 {}",
+                        file.source, repo, package.version, file.sha256, actual
+                    )
+                    .into(),
+                ));
+            }
+
+            copies.push((file, source, destination));
+        }
+
+        for (file, source, destination) in copies {
+            if let Some(parent) = destination.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            fs::copy(&source, &destination).await?;
+            log::info!(
+                "Copied locked file: {} -> {}",
+                file.source,
+                destination.display()
+            );
+        }
+
+        let mut files = package.file_sources.clone();
+        for file in &mut files {
+            file.patched_sha256 = None;
+        }
+        let patches = self
+            .apply_patches(repo, &package.version, &mut files)
+            .await?;
+
+        Ok((files, patches))
+    }
+
+    fn workspace_relative(&self, path: &Path) -> String {
+        let root = self.workspace.get_workspace_info().root;
+        let relative = path.strip_prefix(&root).unwrap_or(path);
+        relative.to_string_lossy().replace('\\', "/")
+    }
+
+    fn workspace_path(&self, path: &str) -> PathBuf {
+        self.workspace.get_workspace_info().root.join(path)
+    }
+
+    /** Reconciles opencli.toml, opencli.lock and the files on disk
+     *
+     * # Plan
+     * - **install**: in opencli.toml but not in opencli.lock
+     * - **upgrade** / **downgrade**: installed version no longer satisfies the constraint
+     * - **reinstall**: the target folder changed
+     * - **repair**: installed files are missing or modified
+     * - **repatch**: files in `patches/` were added, changed or removed
+     * - **remove**: in opencli.lock but no longer in opencli.toml
+     *
+     * # Arguments
+     * * `dry_run` - Only print the plan
+     * * `jobs` - Maximum number of packages downloaded in parallel
+     */
+    pub async fn sync_packages(&mut self, dry_run: bool, jobs: usize) -> Result<()> {
+        let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+        let mut lock = PackageLock::load_from_file(&self.lock_path).await?;
+
+        let spinner = self.create_spinner("Resolving dependencies...");
+        let plan = match self.resolve_packages(&config, &lock, None, None).await {
+            Ok(resolved) => self
+                .plan_sync(&resolved, &lock, &spinner)
+                .await
+                .map(|plan| (plan, resolved)),
+            Err(e) => Err(e),
+        };
+        spinner.finish_and_clear();
+        let (plan, resolved) = plan?;
+
+        if plan.is_empty() {
+            if !dry_run && Self::record_dependencies(&mut lock, &resolved) {
+                lock.save_to_file(&self.lock_path).await?;
+            }
+            println!("Packages are in sync");
+            return Ok(());
+        }
+
+        println!("Sync plan:");
+        for step in &plan {
+            println!("  {}", step.describe());
+        }
+
+        if dry_run {
+            println!("Dry run, no changes made");
+            return Ok(());
+        }
+
+        let mut failures = Vec::new();
+
+        // Removals and lock-only updates need no downloads
+        for step in &plan {
+            match &step.action {
+                SyncAction::Remove { .. } => {
+                    if let Some(package) = lock.remove_package(&step.repo) {
+                        self.remove_installed_files(&step.repo, &package).await?;
+                        println!(
+                            "Removed {} {}",
+                            step.repo,
+                            display_version(&package.version)
+                        );
+                        log::info!("Package removed by sync: {}", step.repo);
+                    }
+                }
+                SyncAction::Relock {
+                    constraint, source, ..
+                } => {
+                    if let Some(package) = lock.installed.get_mut(&step.repo) {
+                        package.constraint = constraint.clone();
+                        package.source = source.clone();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let fetches: Vec<&SyncStep> = plan.iter().filter(|step| step.needs_download()).collect();
+
+        if !fetches.is_empty() {
+            self.workspace.ensure_workspace_structure().await?;
+
+            let multi = MultiProgress::new();
+            let this: &Self = self;
+            let mut prepared: Vec<(SmolStr, Result<SyncPrepared>)> = stream::iter(fetches)
+                .map(|step| {
+                    let pb = multi.add(ProgressBar::new_spinner());
+                    pb.set_style(Self::spinner_style());
+                    pb.set_message(format!("{}: waiting", step.repo));
+                    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+                    async move {
+                        let result = this.prepare_sync_step(step, &pb).await;
+
+                        pb.set_style(Self::spinner_style());
+                        match &result {
+                            Ok(_) => pb.finish_with_message(format!("{}: ready", step.repo)),
+                            Err(_) => pb.abandon_with_message(format!("{}: failed", step.repo)),
+                        }
+
+                        (step.repo.clone(), result)
+                    }
+                })
+                .buffer_unordered(jobs.max(1))
+                .collect()
+                .await;
+            prepared.sort_by(|a, b| a.0.cmp(&b.0));
+
+            for (repo, result) in prepared {
+                let result = match result {
+                    Ok(prepared) => self.apply_sync_step(&repo, prepared, &mut lock).await,
+                    Err(e) => Err(e),
+                };
+
+                match result {
+                    Ok(version) => {
+                        println!("Synced {} {}", repo, version);
+                        log::info!("Package synced: {} {}", repo, version);
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to sync {}: {}", repo, e);
+                        log::error!("Package sync failed: {} - {}", repo, e);
+                        failures.push(repo.to_string());
+                    }
+                }
+            }
+        }
+
+        Self::record_dependencies(&mut lock, &resolved);
+        lock.save_to_file(&self.lock_path).await?;
+        self.config_manager
+            .update_legacy_plugins(&self.lock_path)
+            .await?;
+
+        if !failures.is_empty() {
+            return Err(OpenCliError::Process(
+                format!("Failed to sync packages: {}", failures.join(", ")).into(),
+            ));
+        }
 
+        println!("Packages are in sync");
         Ok(())
     }
 
-    pub async fn install_all_packages(&mut self) -> Result<()> {
+    /** Syncs again when a `[patch]` path changed since it was copied
+     *
+     * Run before a build so edits in a patched checkout are compiled without
+     * a manual `opencli package sync`.
+     */
+    pub async fn refresh_patches(&mut self, jobs: usize) -> Result<()> {
         let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+        let lock = PackageLock::load_from_file(&self.lock_path).await?;
 
-        if let Some(packages) = config.get_packages() {
-            for (repo, spec) in packages {
-                let version = spec.version();
-                let target = spec.target().cloned();
+        let mut stale = false;
+        for (repo, path) in config.get_patches()? {
+            let Some(package) = lock.get_package(&repo) else {
+                continue;
+            };
+
+            let source = PackageSource::Path { path: path.clone() };
+            let version = self.downloader.resolve_pinned(&source).await?;
+            if package.patch.as_ref() != Some(&path)
+                || package.version != version
+                || !self.installed_files_intact(package).await
+            {
+                stale = true;
+                break;
+            }
+        }
 
-                println!("Installing package: {} = {}", repo, version);
-                if let Err(e) = self.install_package(repo, Some(version), target).await {
-                    eprintln!("Failed to install {}: {}", repo, e);
-                    log::error!("Package installation failed: {} - {}", repo, e);
+        if stale {
+            println!("Patched packages changed, syncing");
+            self.sync_packages(false, jobs).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn plan_sync(
+        &self,
+        resolved: &BTreeMap<SmolStr, ResolvedPackage>,
+        lock: &PackageLock,
+        progress: &ProgressBar,
+    ) -> Result<Vec<SyncStep>> {
+        let mut plan = Vec::new();
+
+        for (repo, package) in resolved {
+            let Some(locked) = lock.get_package(repo) else {
+                plan.push(SyncStep::new(
+                    repo,
+                    SyncAction::Install {
+                        package: package.clone(),
+                    },
+                ));
+                continue;
+            };
+
+            let platforms_changed = platforms_changed(locked, &package.platforms);
+            let filters_changed = locked.asset_patterns != package.asset_patterns
+                || locked.excluded_files != package.excluded_files;
+            if locked.version == package.version
+                && locked.target == package.target
+                && !platforms_changed
+                && !filters_changed
+            {
+                progress.set_message(format!("Checking files of {}", repo));
+                let intact = self.installed_files_intact(locked).await;
+                let patches_changed = !self.patches_match(repo, locked).await?;
+                if (!intact || patches_changed) && package.patch.is_some() {
+                    // Patched files are copied again from the local path
+                    plan.push(SyncStep::new(
+                        repo,
+                        SyncAction::Refresh {
+                            from: locked.version.clone(),
+                            package: package.clone(),
+                        },
+                    ));
+                } else if !intact || (patches_changed && locked.file_sources.is_empty()) {
+                    let release = if locked.file_sources.is_empty() {
+                        // Older lock entries can only be repaired from their release
+                        Some(
+                            self.downloader
+                                .find_matching_version(
+                                    repo,
+                                    &VersionConstraint::parse(&locked.version)?,
+                                )
+                                .await?,
+                        )
+                    } else {
+                        None
+                    };
+
+                    // Same version, target, platforms and filters, so the resolved
+                    // selection also covers entries locked before they were recorded
+                    let mut repaired = locked.clone();
+                    repaired.platforms = package.platforms.clone();
+                    repaired.asset_patterns = package.asset_patterns.clone();
+                    repaired.excluded_files = package.excluded_files.clone();
+
+                    plan.push(SyncStep::new(
+                        repo,
+                        SyncAction::Repair {
+                            package: repaired,
+                            release,
+                        },
+                    ));
+                } else if patches_changed {
+                    plan.push(SyncStep::new(
+                        repo,
+                        SyncAction::Repatch {
+                            package: locked.clone(),
+                        },
+                    ));
+                } else {
+                    let constraint = (package.direct
+                        && package
+                            .source
+                            .as_ref()
+                            .is_none_or(PackageSource::is_versioned))
+                    .then(|| package.constraint.clone());
+                    if (package.direct && locked.constraint != constraint)
+                        || locked.source != package.source
+                    {
+                        plan.push(SyncStep::new(
+                            repo,
+                            SyncAction::Relock {
+                                version: locked.version.clone(),
+                                constraint,
+                                source: package.source.clone(),
+                            },
+                        ));
+                    }
                 }
+                continue;
             }
-        } else {
-            println!("No packages defined in configuration");
+
+            let direction = match (
+                Version::parse(&locked.version),
+                Version::parse(&package.version),
+            ) {
+                (Ok(from), Ok(to)) => to.cmp(&from),
+                _ => std::cmp::Ordering::Equal,
+            };
+
+            let from = locked.version.clone();
+            let package = package.clone();
+            let action = match direction {
+                _ if locked.patch.is_some()
+                    || package.patch.is_some()
+                    || package
+                        .source
+                        .as_ref()
+                        .is_some_and(|source| !source.is_versioned()) =>
+                {
+                    SyncAction::Refresh { from, package }
+                }
+                std::cmp::Ordering::Greater => SyncAction::Upgrade { from, package },
+                std::cmp::Ordering::Less => SyncAction::Downgrade { from, package },
+                std::cmp::Ordering::Equal => SyncAction::Reinstall {
+                    reason: if locked.target != package.target {
+                        "target changed"
+                    } else if platforms_changed {
+                        "platforms changed"
+                    } else if filters_changed {
+                        "asset or file filters changed"
+                    } else {
+                        "version changed"
+                    },
+                    package,
+                },
+            };
+            plan.push(SyncStep::new(repo, action));
+        }
+
+        let mut removed: Vec<(&str, &InstalledPackage)> = lock
+            .list_packages()
+            .into_iter()
+            .filter(|(repo, _)| !resolved.contains_key(*repo))
+            .collect();
+        removed.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (repo, package) in removed {
+            plan.push(SyncStep::new(
+                repo,
+                SyncAction::Remove {
+                    version: package.version.clone(),
+                },
+            ));
+        }
+
+        Ok(plan)
+    }
+
+    async fn prepare_sync_step(
+        &self,
+        step: &SyncStep,
+        progress: &ProgressBar,
+    ) -> Result<SyncPrepared> {
+        let (release, constraint, selection, source) = match &step.action {
+            SyncAction::Install { package }
+            | SyncAction::Upgrade { package, .. }
+            | SyncAction::Downgrade { package, .. }
+            | SyncAction::Refresh { package, .. }
+            | SyncAction::Reinstall { package, .. } => {
+                let prepared = self.prepare_resolved(&step.repo, package, progress).await?;
+                return Ok(SyncPrepared::Package(prepared));
+            }
+            SyncAction::Repair {
+                package,
+                release: Some(release),
+            } => (
+                release,
+                package
+                    .constraint
+                    .clone()
+                    .unwrap_or_else(|| package.version.clone()),
+                PackageSelection {
+                    target: package.target.clone(),
+                    platforms: package.platforms.clone(),
+                    asset_patterns: package.asset_patterns.clone(),
+                    excluded_files: package.excluded_files.clone(),
+                },
+                package.source.clone(),
+            ),
+            SyncAction::Repair {
+                package,
+                release: None,
+            }
+            | SyncAction::Repatch { package } => {
+                let temp_dir = self.get_temp_dir(&step.repo)?;
+                let downloaded = self
+                    .downloader
+                    .download_locked_assets(
+                        &step.repo,
+                        package.source.as_ref(),
+                        &package.version,
+                        &package.assets,
+                        &temp_dir,
+                        Some(progress),
+                    )
+                    .await;
+                self.cleanup_on_error(&temp_dir, downloaded).await?;
+                return Ok(SyncPrepared::Locked {
+                    package: package.clone(),
+                    temp_dir,
+                });
+            }
+            SyncAction::Remove { .. } | SyncAction::Relock { .. } => {
+                unreachable!("step needs no download")
+            }
+        };
+
+        let prepared = self
+            .prepare_release(
+                &step.repo,
+                release.clone(),
+                &constraint,
+                selection,
+                source,
+                progress,
+            )
+            .await?;
+        Ok(SyncPrepared::Package(prepared))
+    }
+
+    async fn apply_sync_step(
+        &self,
+        repo: &str,
+        prepared: SyncPrepared,
+        lock: &mut PackageLock,
+    ) -> Result<SmolStr> {
+        match prepared {
+            SyncPrepared::Locked { package, temp_dir } => {
+                let applied = self.apply_locked_files(repo, &package, &temp_dir).await;
+                self.cleanup_temp_dir(&temp_dir).await?;
+                let (file_sources, patches) = applied?;
+
+                if let Some(entry) = lock.installed.get_mut(repo) {
+                    entry.file_sources = file_sources;
+                    entry.patches = patches;
+                }
+                Ok(display_version(&package.version).into())
+            }
+            SyncPrepared::Package(prepared) => {
+                let version: SmolStr = display_version(&prepared.release.tag_name).into();
+                self.replace_package(prepared, lock).await?;
+                Ok(version)
+            }
+        }
+    }
+
+    async fn installed_files_intact(&self, package: &InstalledPackage) -> bool {
+        if !package.file_sources.is_empty() {
+            return self.locked_files_intact(package).await;
+        }
+
+        package
+            .files
+            .iter()
+            .all(|file| Path::new(file.as_str()).exists())
+    }
+
+    async fn remove_installed_files(&self, repo: &str, package: &InstalledPackage) -> Result<()> {
+        self.remove_package_files_from_lock(&package.files).await?;
+
+        for file_path in &package.files {
+            if let Some(file_name) = Path::new(file_path.as_str())
+                .file_name()
+                .and_then(|n| n.to_str())
+            {
+                let _ = self.cache.remove_hash(file_name).await;
+            }
+        }
+
+        if let Some(PackageTarget::Plugins) = &package.target {
+            self.config_manager
+                .remove_legacy_plugin_advanced(repo, package)
+                .await?;
         }
 
         Ok(())
@@ -165,73 +1629,354 @@ impl PackageManager {
         }
 
         spinner.set_message("Removing package files...");
-        let removed_package = lock.get_package(repo).cloned();
-        if let Some(package) = &removed_package {
-            self.remove_package_files_from_lock(&package.files).await?;
+        if let Some(package) = lock.get_package(repo).cloned() {
+            self.remove_installed_files(repo, &package).await?;
         }
 
-        spinner.set_message("Updating cache...");
-        if let Some(package) = &removed_package {
-            for file_path in &package.files {
-                if let Some(file_name) = std::path::Path::new(file_path.as_str())
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                {
-                    let _ = self.cache.remove_hash(file_name).await;
+        spinner.set_message("Updating lock file...");
+        lock.remove_package(repo);
+        lock.save_to_file(&self.lock_path).await?;
+
+        spinner.set_message("Updating configuration...");
+        self.remove_from_config(repo).await?;
+
+        spinner.finish_with_message(format!("Successfully removed {}", repo));
+        log::info!("Package removed: {}", repo);
+
+        Ok(())
+    }
+
+    pub async fn list_packages(&self) -> Result<()> {
+        let lock = PackageLock::load_from_file(&self.lock_path).await?;
+
+        let packages = lock.list_packages();
+        if !packages.is_empty() {
+            println!("Installed packages:");
+            for (repo, package) in packages {
+                let target_info = package
+                    .target
+                    .as_ref()
+                    .map(|t| {
+                        format!(
+                            " ({})",
+                            match t {
+                                PackageTarget::Components => "components",
+                                PackageTarget::Plugins => "plugins",
+                            }
+                        )
+                    })
+                    .unwrap_or_default();
+
+                let patched = if package.patch.is_some() {
+                    " [patched]"
+                } else {
+                    ""
+                };
+                println!("  {} = {}{}{}", repo, package.version, target_info, patched);
+                if let Some(source) = &package.source {
+                    println!("    Source: {}", source);
+                }
+                if let Some(path) = &package.patch {
+                    println!("    Patched: files copied from {} instead", path);
+                }
+                if !package.platforms.is_empty() && platform_specific(package) {
+                    println!("    Platforms: {}", platform_list(&package.platforms));
                 }
+                if !package.asset_patterns.is_empty() {
+                    println!("    Assets: {}", package.asset_patterns.join(", "));
+                }
+                if !package.excluded_files.is_empty() {
+                    println!("    Excluded: {}", package.excluded_files.join(", "));
+                }
+                println!("    Installed: {}", package.installed_at);
+                println!("    Hash: {}", &package.hash[..32]);
+                println!("    Files: {}", package.files.len());
             }
+        } else {
+            println!("No packages installed");
         }
 
-        spinner.set_message("Updating config.json...");
-        if let Some(package) = &removed_package {
-            if let Some(PackageTarget::Plugins) = &package.target {
-                self.config_manager
-                    .remove_legacy_plugin_advanced(repo, package)
-                    .await?;
+        Ok(())
+    }
+
+    /** Reports installed, allowed and newest versions of every locked package
+     *
+     * # Arguments
+     * * `json` - Print a machine readable report instead of a table
+     */
+    pub async fn outdated_packages(&self, json: bool) -> Result<()> {
+        let lock = PackageLock::load_from_file(&self.lock_path).await?;
+        let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+
+        let mut packages = lock.list_packages();
+        packages.sort_by(|a, b| a.0.cmp(b.0));
+
+        let spinner = (!json).then(|| self.create_spinner("Fetching releases..."));
+        let mut report = Vec::new();
+
+        for (repo, package) in packages {
+            if let Some(spinner) = &spinner {
+                spinner.set_message(format!("Fetching releases of {}", repo));
+            }
+
+            let spec = config
+                .get_packages()
+                .and_then(|packages| packages.get(repo));
+
+            let source = match (&package.patch, spec) {
+                // A patched package is compared with its local path, not its release
+                (Some(path), _) => Some(PackageSource::Path { path: path.clone() }),
+                (None, Some(spec)) => spec.source(repo)?,
+                (None, None) => package.source.clone(),
+            };
+
+            let entry = match source {
+                Some(source) if !source.is_versioned() => {
+                    self.outdated_pinned(repo, package, &source).await
+                }
+                source => {
+                    let constraint = spec
+                        .map(|spec| SmolStr::from(spec.version()))
+                        .or_else(|| package.constraint.clone());
+                    self.outdated_entry(repo, package, constraint, source.as_ref())
+                        .await
+                }
+            };
+            report.push(entry);
+        }
+
+        if let Some(spinner) = spinner {
+            spinner.finish_and_clear();
+        }
+
+        if json {
+            let output = serde_json::to_string_pretty(&report).map_err(|e| {
+                OpenCliError::Process(format!("Failed to serialize report: {}", e).into())
+            })?;
+            println!("{}", output);
+            return Ok(());
+        }
+
+        if report.is_empty() {
+            println!("No packages installed");
+            return Ok(());
+        }
+
+        let width = report
+            .iter()
+            .map(|entry| entry.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("Package".len());
+
+        println!(
+            "{:<width$}  {:<12}  {:<12}  Latest",
+            "Package", "Installed", "Wanted"
+        );
+        for entry in &report {
+            let show = |version: &Option<String>| version.clone().unwrap_or_else(|| "-".into());
+
+            print!(
+                "{:<width$}  {:<12}  {:<12}  {}",
+                entry.name,
+                entry.installed,
+                show(&entry.wanted),
+                show(&entry.latest)
+            );
+            match &entry.error {
+                Some(error) => println!("  ({})", error),
+                None if entry.outdated => println!("  outdated"),
+                None => println!(),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn outdated_entry(
+        &self,
+        repo: &str,
+        package: &InstalledPackage,
+        constraint: Option<SmolStr>,
+        source: Option<&PackageSource>,
+    ) -> OutdatedPackage {
+        let mut entry = OutdatedPackage {
+            name: repo.to_string(),
+            installed: package.version.to_string(),
+            constraint: constraint.as_ref().map(|c| c.to_string()),
+            wanted: None,
+            latest: None,
+            outdated: false,
+            error: None,
+        };
+
+        let releases = match self.downloader.releases_for(repo, source).await {
+            Ok(releases) => releases,
+            Err(e) => {
+                entry.error = Some(e.to_string());
+                return entry;
+            }
+        };
+
+        if let Some(constraint) = &constraint {
+            match VersionConstraint::parse(constraint) {
+                Ok(constraint) => {
+                    entry.wanted = PackageDownloader::latest_release(&releases, &constraint)
+                        .map(|release| release.tag_name.clone());
+                }
+                Err(e) => entry.error = Some(e.to_string()),
+            }
+        }
+
+        let stable: Vec<GitHubRelease> = releases
+            .iter()
+            .filter(|release| !release.prerelease)
+            .cloned()
+            .collect();
+        let any = VersionConstraint::parse("*").expect("wildcard constraint");
+        entry.latest = PackageDownloader::latest_release(&stable, &any)
+            .or_else(|| PackageDownloader::latest_release(&releases, &any))
+            .map(|release| release.tag_name.clone());
+
+        let installed = Version::parse(&package.version).ok();
+        entry.outdated = [&entry.wanted, &entry.latest].into_iter().any(|candidate| {
+            match (candidate.as_deref().map(Version::parse), &installed) {
+                (Some(Ok(candidate)), Some(installed)) => &candidate > installed,
+                _ => false,
+            }
+        });
+
+        entry
+    }
+
+    /// Compares the locked version of a pinned package with what its source resolves to now
+    async fn outdated_pinned(
+        &self,
+        repo: &str,
+        package: &InstalledPackage,
+        source: &PackageSource,
+    ) -> OutdatedPackage {
+        let mut entry = OutdatedPackage {
+            name: repo.to_string(),
+            installed: display_version(&package.version).to_string(),
+            constraint: Some(source.to_string()),
+            wanted: None,
+            latest: None,
+            outdated: false,
+            error: None,
+        };
+
+        // A commit pinned by rev cannot move, no need to ask the remote
+        let pinned = match source {
+            PackageSource::Git(git) if git.rev.is_some() && git.accepts(&package.version) => {
+                Ok(package.version.clone())
+            }
+            _ => self.downloader.resolve_pinned(source).await,
+        };
+
+        match pinned {
+            Ok(version) => {
+                entry.outdated = version != package.version;
+                entry.wanted = Some(display_version(&version).to_string());
             }
+            Err(e) => entry.error = Some(e.to_string()),
         }
 
-        spinner.set_message("Updating lock file...");
-        lock.remove_package(repo);
-        lock.save_to_file(&self.lock_path).await?;
+        entry
+    }
 
-        spinner.set_message("Updating configuration...");
-        self.remove_from_config(repo).await?;
+    /// Prints the locked dependency graph starting from the top-level packages
+    pub async fn print_tree(&self) -> Result<()> {
+        let lock = PackageLock::load_from_file(&self.lock_path).await?;
 
-        spinner.finish_with_message(format!("Successfully removed {}", repo));
-        log::info!("Package removed: {}", repo);
+        let roots = top_level_packages(&lock);
+        if roots.is_empty() {
+            println!("No packages installed");
+            return Ok(());
+        }
+
+        let mut printed = HashSet::new();
+        for repo in roots {
+            let mut path = Vec::new();
+            print_tree_node(&lock, repo, "", "", &mut path, &mut printed);
+        }
 
         Ok(())
     }
 
-    pub async fn list_packages(&self) -> Result<()> {
+    /** Explains why a package or file is installed
+     *
+     * # Arguments
+     * * `query` - A package (`owner/repo`), an installed file path, or a bare file name
+     */
+    pub async fn explain(&self, query: &str) -> Result<()> {
         let lock = PackageLock::load_from_file(&self.lock_path).await?;
 
-        let packages = lock.list_packages();
-        if !packages.is_empty() {
-            println!("Installed packages:");
-            for (repo, package) in packages {
-                let target_info = package
-                    .target
-                    .as_ref()
-                    .map(|t| {
-                        format!(
-                            " ({})",
-                            match t {
-                                PackageTarget::Components => "components",
-                                PackageTarget::Plugins => "plugins",
-                            }
-                        )
+        let repos: Vec<&str> = match lock
+            .list_packages()
+            .into_iter()
+            .find(|(repo, _)| repo.eq_ignore_ascii_case(query))
+        {
+            Some((repo, _)) => vec![repo],
+            None => {
+                let mut owners: Vec<&str> = lock
+                    .list_packages()
+                    .into_iter()
+                    .filter_map(|(repo, package)| {
+                        let file = package.files.iter().find(|f| file_matches(f, query))?;
+                        println!(
+                            "{} belongs to {} {}",
+                            file,
+                            repo,
+                            display_version(&package.version)
+                        );
+                        Some(repo)
                     })
-                    .unwrap_or_default();
+                    .collect();
+                owners.sort();
+                owners
+            }
+        };
 
-                println!("  {} = {}{}", repo, package.version, target_info);
-                println!("    Installed: {}", package.installed_at);
-                println!("    Hash: {}", &package.hash[..32]);
-                println!("    Files: {}", package.files.len());
+        if repos.is_empty() {
+            return Err(OpenCliError::NotFound(
+                format!("No installed package or file matches {}", query).into(),
+            ));
+        }
+
+        for repo in repos {
+            let chains = dependency_chains(&lock, repo);
+            let version = lock.get_installed_version(repo).unwrap_or_default();
+
+            if chains.iter().any(|chain| chain.len() == 1) {
+                println!("{} {} is listed in opencli.toml", repo, version);
+            }
+
+            let pulled_in: Vec<&Vec<&str>> =
+                chains.iter().filter(|chain| chain.len() > 1).collect();
+            if !pulled_in.is_empty() {
+                println!("{} {} is required by:", repo, version);
+                for chain in pulled_in {
+                    let described: Vec<String> = chain
+                        .iter()
+                        .map(|repo| {
+                            format!(
+                                "{} {}",
+                                repo,
+                                lock.get_installed_version(repo).unwrap_or_default()
+                            )
+                        })
+                        .collect();
+                    println!("  {}", described.join(" -> "));
+                }
+            }
+
+            if chains.is_empty() {
+                println!(
+                    "{} {} is not required by anything, `opencli package sync` will remove it",
+                    repo, version
+                );
             }
-        } else {
-            println!("No packages installed");
         }
 
         Ok(())
@@ -250,7 +1995,11 @@ impl PackageManager {
         let mut all_valid = true;
 
         for (repo, package) in packages {
-            print!("Checking {} {}... ", repo, package.version);
+            print!(
+                "Checking {} {}... ",
+                repo,
+                display_version(&package.version)
+            );
 
             let mut files_exist = true;
             let mut valid_files = Vec::new();
@@ -297,49 +2046,309 @@ impl PackageManager {
         Ok(())
     }
 
-    pub async fn update_package(&mut self, repo: &str) -> Result<()> {
+    /** Updates packages to the newest release allowed by their constraint
+     *
+     * # Arguments
+     * * `repo` - Package to update, or every installed package when `None`
+     * * `dry_run` - Only print the plan and release notes
+     * * `jobs` - Maximum number of packages downloaded in parallel
+     *
+     * # Notes
+     * - Everything is downloaded before any file is touched
+     * - Old files are moved aside and restored if installing the new release fails
+     */
+    pub async fn update_packages(
+        &mut self,
+        repo: Option<&str>,
+        dry_run: bool,
+        jobs: usize,
+    ) -> Result<()> {
         let config = BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
-        let lock = PackageLock::load_from_file(&self.lock_path).await?;
-
-        if let Some(packages) = config.get_packages() {
-            if let Some(spec) = packages.get(repo) {
-                let _constraint = VersionConstraint::parse(spec.version())?;
-                let target = spec.target().cloned();
+        let mut lock = PackageLock::load_from_file(&self.lock_path).await?;
+        let packages = config
+            .get_packages()
+            .ok_or_else(|| OpenCliError::NotFound("No packages installed".into()))?;
 
-                if let Some(package) = lock.get_package(repo) {
-                    self.remove_package_files_from_lock(&package.files).await?;
-                }
-                self.install_package(repo, Some(spec.version()), target)
-                    .await?;
-            } else {
+        if let Some(repo) = repo {
+            if !packages.contains_key(repo) {
                 return Err(OpenCliError::NotFound(
                     format!("Package {} not found", repo).into(),
                 ));
             }
-        } else {
-            return Err(OpenCliError::NotFound("No packages installed".into()));
+        }
+
+        let spinner = self.create_spinner("Checking for updates...");
+        let resolved = match self
+            .resolve_packages(&config, &lock, None, Some(repo))
+            .await
+        {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                spinner.finish_and_clear();
+                return Err(e);
+            }
+        };
+
+        let mut plan = Vec::new();
+        for (name, package) in &resolved {
+            let installed = lock.get_package(name);
+            if installed.is_some_and(|installed| installed.version == package.version) {
+                continue;
+            }
+
+            let notes = match installed {
+                Some(installed)
+                    if package.patch.is_none()
+                        && package
+                            .source
+                            .as_ref()
+                            .is_none_or(PackageSource::is_versioned) =>
+                {
+                    spinner.set_message(format!("Fetching release notes of {}", name));
+                    let releases = self
+                        .downloader
+                        .releases_for(name, package.source.as_ref())
+                        .await
+                        .unwrap_or_default();
+                    release_notes_between(&releases, &installed.version, &package.version)
+                }
+                _ => Vec::new(),
+            };
+
+            plan.push(PackageUpdate {
+                repo: name.clone(),
+                from: installed.map(|installed| installed.version.clone()),
+                package: package.clone(),
+                notes,
+            });
+        }
+        spinner.finish_and_clear();
+
+        if plan.is_empty() {
+            println!("All packages are up to date");
+            return Ok(());
+        }
+
+        println!("Update plan:");
+        for update in &plan {
+            let to = display_version(&update.package.version);
+            match &update.from {
+                Some(from) => println!("  {} {} -> {}", update.repo, display_version(from), to),
+                None => println!("  {} {} (new dependency)", update.repo, to),
+            }
+            if let Some(source) = &update.package.source {
+                println!("    {}", source);
+            }
+            if let Some(path) = &update.package.patch {
+                println!("    patched from {}", path);
+            }
+            for (tag, body) in &update.notes {
+                println!("    {}", tag);
+                match body.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+                    Some(body) => {
+                        for line in body.lines() {
+                            println!("      {}", line.trim_end());
+                        }
+                    }
+                    None => println!("      (no release notes)"),
+                }
+            }
+        }
+
+        if dry_run {
+            println!("Dry run, no changes made");
+            return Ok(());
+        }
+
+        self.workspace.ensure_workspace_structure().await?;
+
+        let multi = MultiProgress::new();
+        let this: &Self = self;
+        let mut prepared: Vec<(SmolStr, Result<PreparedPackage>)> = stream::iter(&plan)
+            .map(|update| {
+                let pb = multi.add(ProgressBar::new_spinner());
+                pb.set_style(Self::spinner_style());
+                pb.set_message(format!("{}: waiting", update.repo));
+                pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+                async move {
+                    let result = this
+                        .prepare_resolved(&update.repo, &update.package, &pb)
+                        .await;
+
+                    match &result {
+                        Ok(package) => pb.finish_with_message(format!(
+                            "{}: {} ready",
+                            update.repo,
+                            display_version(&package.release.tag_name)
+                        )),
+                        Err(_) => pb.abandon_with_message(format!("{}: failed", update.repo)),
+                    }
+
+                    (update.repo.clone(), result)
+                }
+            })
+            .buffer_unordered(jobs.max(1))
+            .collect()
+            .await;
+        prepared.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut failures = Vec::new();
+        for (repo, result) in prepared {
+            let result = match result {
+                Ok(package) => {
+                    let tag_name = display_version(&package.release.tag_name).to_string();
+                    self.replace_package(package, &mut lock)
+                        .await
+                        .map(|_| tag_name)
+                }
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(tag_name) => {
+                    println!("Updated {} to {}", repo, tag_name);
+                    log::info!("Package updated: {} {}", repo, tag_name);
+                }
+                Err(e) => {
+                    eprintln!("Failed to update {}: {}", repo, e);
+                    log::error!("Package update failed: {} - {}", repo, e);
+                    failures.push(repo.to_string());
+                }
+            }
+        }
+
+        Self::record_dependencies(&mut lock, &resolved);
+        lock.save_to_file(&self.lock_path).await?;
+        self.config_manager
+            .update_legacy_plugins(&self.lock_path)
+            .await?;
+
+        if !failures.is_empty() {
+            return Err(OpenCliError::Process(
+                format!("Failed to update packages: {}", failures.join(", ")).into(),
+            ));
         }
 
         Ok(())
     }
 
+    /// Swaps an installed package for a downloaded one, restoring the old files on failure
+    async fn replace_package(
+        &self,
+        package: PreparedPackage,
+        lock: &mut PackageLock,
+    ) -> Result<()> {
+        let repo = package.repo.clone();
+        let Some(previous) = lock.remove_package(&repo) else {
+            return self.apply_package(package, lock).await;
+        };
+
+        let backup_dir = self
+            .workspace
+            .get_workspace_info()
+            .root
+            .join(format!(".opencli-backup-{}", repo.replace('/', "_")));
+        let prepared = match self.cleanup_temp_dir(&backup_dir).await {
+            Ok(()) => fs::create_dir_all(&backup_dir).await.map_err(Into::into),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = self.cleanup_on_error(&package.temp_dir, prepared).await {
+            lock.insert_package(repo.clone(), previous);
+            return Err(e);
+        }
+
+        let mut backups = Vec::new();
+        for (index, file) in previous.files.iter().enumerate() {
+            let path = PathBuf::from(file.as_str());
+            if !path.exists() {
+                continue;
+            }
+
+            let backup = backup_dir.join(index.to_string());
+            if let Err(e) = fs::rename(&path, &backup).await {
+                self.restore_backups(&backups).await;
+                lock.insert_package(repo.clone(), previous);
+                let _ = self.cleanup_temp_dir(&backup_dir).await;
+                let _ = self.cleanup_temp_dir(&package.temp_dir).await;
+                return Err(e.into());
+            }
+            backups.push((path, backup));
+        }
+
+        match self.apply_package(package, lock).await {
+            Ok(()) => {
+                let current: Vec<&str> = lock
+                    .get_package(&repo)
+                    .map(|p| p.files.iter().map(|f| f.as_str()).collect())
+                    .unwrap_or_default();
+
+                for file_path in &previous.files {
+                    if current.contains(&file_path.as_str()) {
+                        continue;
+                    }
+                    self.remove_empty_parents(Path::new(file_path.as_str()))
+                        .await;
+                    if let Some(file_name) = Path::new(file_path.as_str())
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                    {
+                        let _ = self.cache.remove_hash(file_name).await;
+                    }
+                }
+
+                if let Some(PackageTarget::Plugins) = &previous.target {
+                    self.config_manager
+                        .remove_legacy_plugin_advanced(&repo, &previous)
+                        .await?;
+                }
+
+                self.cleanup_temp_dir(&backup_dir).await?;
+                Ok(())
+            }
+            Err(e) => {
+                log::warn!("Restoring previous files of {} after: {}", repo, e);
+                self.restore_backups(&backups).await;
+                lock.insert_package(repo, previous);
+                let _ = self.cleanup_temp_dir(&backup_dir).await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn restore_backups(&self, backups: &[(PathBuf, PathBuf)]) {
+        for (original, backup) in backups {
+            if let Err(e) = fs::rename(backup, original).await {
+                log::error!(
+                    "Failed to restore {} from {}: {}",
+                    original.display(),
+                    backup.display(),
+                    e
+                );
+            }
+        }
+    }
+
     async fn install_package_files(
         &self,
-        _repo: &str,
         package_files: &crate::package::downloader::PackageFiles,
+        temp_dir: &Path,
         target: Option<&PackageTarget>,
-    ) -> Result<Vec<PathBuf>> {
+    ) -> Result<Vec<(PathBuf, PathBuf)>> {
         self.workspace.ensure_workspace_structure().await?;
 
         let mut installed_files = Vec::new();
         let include_paths = self.get_include_paths().await?;
         let workspace_info = self.workspace.get_workspace_info();
 
-        for include_file in &package_files.includes {
+        for (include_file, relative) in include_layout(&package_files.includes, temp_dir) {
             if let Some(include_path) = include_paths.first() {
-                let dest_path = include_path.join(include_file.file_name().unwrap());
-                fs::copy(include_file, &dest_path).await?;
-                installed_files.push(dest_path.clone());
+                let dest_path = include_path.join(relative);
+                if let Some(parent) = dest_path.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+                fs::copy(&include_file, &dest_path).await?;
+                installed_files.push((include_file.clone(), dest_path.clone()));
                 log::info!(
                     "Copied include: {} -> {}",
                     include_file.display(),
@@ -351,7 +2360,7 @@ impl PackageManager {
         for binary_file in &package_files.root_binaries {
             let dest_path = workspace_info.root.join(binary_file.file_name().unwrap());
             fs::copy(binary_file, &dest_path).await?;
-            installed_files.push(dest_path.clone());
+            installed_files.push((binary_file.clone(), dest_path.clone()));
             log::info!(
                 "Copied root binary: {} -> {}",
                 binary_file.display(),
@@ -378,7 +2387,7 @@ impl PackageManager {
                         .components
                         .join(binary_file.file_name().unwrap());
                     fs::copy(binary_file, &dest_path).await?;
-                    installed_files.push(dest_path.clone());
+                    installed_files.push((binary_file.clone(), dest_path.clone()));
                     log::info!(
                         "Copied component binary: {} -> {}",
                         binary_file.display(),
@@ -392,7 +2401,7 @@ impl PackageManager {
                         .plugins
                         .join(binary_file.file_name().unwrap());
                     fs::copy(binary_file, &dest_path).await?;
-                    installed_files.push(dest_path.clone());
+                    installed_files.push((binary_file.clone(), dest_path.clone()));
                     log::info!(
                         "Copied plugin binary: {} -> {}",
                         binary_file.display(),
@@ -400,12 +2409,34 @@ impl PackageManager {
                     );
                 }
             }
+            None if package_files.from_manifest => {
+                let destinations = [
+                    (
+                        &package_files.component_binaries,
+                        &workspace_info.components,
+                    ),
+                    (&package_files.plugin_binaries, &workspace_info.plugins),
+                ];
+
+                for (binaries, folder) in destinations {
+                    for binary_file in binaries {
+                        let dest_path = folder.join(binary_file.file_name().unwrap());
+                        fs::copy(binary_file, &dest_path).await?;
+                        installed_files.push((binary_file.clone(), dest_path.clone()));
+                        log::info!(
+                            "Copied declared binary: {} -> {}",
+                            binary_file.display(),
+                            dest_path.display()
+                        );
+                    }
+                }
+            }
             None => {
                 for binary_file in component_files {
                     let target_folder = self.detect_binary_target(binary_file).await?;
                     let dest_path = target_folder.join(binary_file.file_name().unwrap());
                     fs::copy(binary_file, &dest_path).await?;
-                    installed_files.push(dest_path.clone());
+                    installed_files.push((binary_file.clone(), dest_path.clone()));
                     log::info!(
                         "Copied auto-detected binary: {} -> {}",
                         binary_file.display(),
@@ -415,8 +2446,8 @@ impl PackageManager {
             }
         }
 
-        installed_files.sort();
-        installed_files.dedup();
+        installed_files.sort_by(|a, b| a.1.cmp(&b.1));
+        installed_files.dedup_by(|a, b| a.1 == b.1);
         Ok(installed_files)
     }
 
@@ -454,31 +2485,53 @@ impl PackageManager {
             } else {
                 log::warn!("File not found (already removed?): {}", path.display());
             }
+
+            self.remove_empty_parents(path).await;
         }
         Ok(())
     }
 
-    async fn update_config(
-        &self,
-        repo: &str,
-        version: &str,
-        target: Option<PackageTarget>,
-    ) -> Result<()> {
-        let mut config =
-            BuildConfig::from_file(self.config_path.to_string_lossy().as_ref()).await?;
+    /// Removes folders left empty by a removed file, stopping at the workspace folders
+    async fn remove_empty_parents(&self, file: &Path) {
+        let workspace_info = self.workspace.get_workspace_info();
+        let mut stops = self.get_include_paths().await.unwrap_or_default();
+        stops.extend([
+            workspace_info.root.clone(),
+            workspace_info.components.clone(),
+            workspace_info.plugins.clone(),
+        ]);
+        let stops: Vec<PathBuf> = stops
+            .iter()
+            .map(|stop| {
+                let stop = if stop.is_absolute() {
+                    stop.clone()
+                } else {
+                    workspace_info.root.join(stop)
+                };
+                std::fs::canonicalize(&stop).unwrap_or(stop)
+            })
+            .collect();
 
-        let spec = if let Some(target) = target {
-            PackageSpec::new_detailed(version, Some(target))
-        } else {
-            PackageSpec::new_simple(version)
-        };
+        let mut current = file.parent();
+        while let Some(dir) = current {
+            if dir.as_os_str().is_empty() {
+                break;
+            }
 
-        config.add_package(repo.into(), spec);
-        config
-            .save_to_file(self.config_path.to_string_lossy().as_ref())
-            .await?;
+            let Ok(canonical) = std::fs::canonicalize(dir) else {
+                break;
+            };
+            if stops.contains(&canonical) || !stops.iter().any(|stop| canonical.starts_with(stop)) {
+                break;
+            }
 
-        Ok(())
+            // Fails (and stops the walk) as soon as the folder still has content
+            if fs::remove_dir(dir).await.is_err() {
+                break;
+            }
+            log::info!("Removed empty folder: {}", dir.display());
+            current = dir.parent();
+        }
     }
 
     async fn remove_from_config(&self, repo: &str) -> Result<()> {
@@ -514,7 +2567,12 @@ impl PackageManager {
     }
 
     fn get_temp_dir(&self, repo: &str) -> Result<PathBuf> {
-        let temp_name = repo.replace('/', "_");
+        let temp_name = format!(
+            "{}-{}-{}",
+            repo.replace(['/', '\\', ':'], "_"),
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        );
         let temp_dir = std::env::temp_dir()
             .join("opencli")
             .join("packages")
@@ -529,15 +2587,514 @@ impl PackageManager {
         Ok(())
     }
 
+    /// Passes `result` through, removing `temp_dir` first if it failed
+    async fn cleanup_on_error<T>(&self, temp_dir: &Path, result: Result<T>) -> Result<T> {
+        if result.is_err() {
+            if let Err(e) = self.cleanup_temp_dir(temp_dir).await {
+                log::warn!("Failed to remove {}: {}", temp_dir.display(), e);
+            }
+        }
+        result
+    }
+
     fn create_spinner(&self, message: impl Into<String>) -> ProgressBar {
         let spinner = ProgressBar::new_spinner();
-        spinner.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.green} {msg}")
-                .unwrap(),
-        );
+        spinner.set_style(Self::spinner_style());
         spinner.set_message(message.into());
         spinner.enable_steady_tick(std::time::Duration::from_millis(100));
         spinner
     }
+
+    fn spinner_style() -> ProgressStyle {
+        ProgressStyle::default_spinner()
+            .template("{spinner:.green} {msg}")
+            .unwrap()
+    }
+}
+
+struct PreparedPackage {
+    repo: SmolStr,
+    constraint: SmolStr,
+    release: GitHubRelease,
+    selection: PackageSelection,
+    files: PackageFiles,
+    temp_dir: PathBuf,
+    /// Listed in opencli.toml; transitive packages are locked without a constraint
+    direct: bool,
+    dependencies: Option<Vec<PackageDependency>>,
+    source: Option<PackageSource>,
+    /// Local path the files were taken from instead of `source`
+    patch: Option<SmolStr>,
+}
+
+fn relative_slash_path(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/** Maps each include to its path below the project's include folder
+ *
+ * Paths are kept relative to the first `include` folder in the package
+ * (`include/`, `qawno/include/`, `pawno/include/`). Includes outside such a
+ * folder keep their path below the deepest folder they all share, so
+ * `YSI-Includes-5.10/YSI_Coding/y_hooks.inc` installs as `YSI_Coding/y_hooks.inc`.
+ */
+fn include_layout(includes: &[PathBuf], temp_dir: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let parts: Vec<Vec<String>> = includes
+        .iter()
+        .map(|include| {
+            include
+                .strip_prefix(temp_dir)
+                .unwrap_or(include)
+                .components()
+                .filter_map(|c| match c {
+                    std::path::Component::Normal(part) => Some(part.to_string_lossy().into()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .collect();
+
+    let root_of = |parts: &[String]| {
+        parts[..parts.len().saturating_sub(1)]
+            .iter()
+            .position(|part| part.eq_ignore_ascii_case("include"))
+            .map(|index| index + 1)
+    };
+
+    // Shared folder prefix of the includes that have no include root
+    let unrooted: Vec<&Vec<String>> = parts.iter().filter(|p| root_of(p).is_none()).collect();
+    let shared = unrooted.first().map_or(0, |first| {
+        let folders = &first[..first.len().saturating_sub(1)];
+        (0..folders.len())
+            .take_while(|&i| {
+                unrooted
+                    .iter()
+                    .all(|p| p.len() > i + 1 && p[i] == folders[i])
+            })
+            .count()
+    });
+
+    includes
+        .iter()
+        .zip(&parts)
+        .filter(|(_, parts)| !parts.is_empty())
+        .map(|(include, parts)| {
+            let skip = root_of(parts).unwrap_or(shared);
+            (include.clone(), parts[skip..].iter().collect())
+        })
+        .collect()
+}
+
+/// Index of the installed file a diff changes, accepting `a/` and `b/` prefixes from `git diff`
+fn patched_file(files: &[LockedFile], path: &str) -> Option<usize> {
+    let stripped = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+
+    files
+        .iter()
+        .position(|file| file.destination == path)
+        .or_else(|| files.iter().position(|file| file.destination == stripped))
+}
+
+/// Whether any asset or installed file of `package` is built for one platform only
+fn platform_specific(package: &InstalledPackage) -> bool {
+    package
+        .assets
+        .iter()
+        .map(|asset| asset.name.as_str())
+        .chain(
+            package
+                .files
+                .iter()
+                .filter_map(|file| Path::new(file.as_str()).file_name()?.to_str()),
+        )
+        .any(|name| Platform::of_name(name).is_some())
+}
+
+/// Whether `locked` was installed for other platforms than `platforms` in a way that matters
+fn platforms_changed(locked: &InstalledPackage, platforms: &[Platform]) -> bool {
+    locked.platforms != platforms && platform_specific(locked)
+}
+
+fn safe_join(base: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+
+    relative
+        .components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)))
+        .then(|| base.join(relative))
+}
+
+struct PackageUpdate {
+    repo: SmolStr,
+    /// Installed version, `None` for a dependency that is new
+    from: Option<SmolStr>,
+    package: ResolvedPackage,
+    /// Tag and body of every release after `from` up to and including the new version
+    notes: Vec<(String, Option<String>)>,
+}
+
+#[derive(Debug, Serialize)]
+struct OutdatedPackage {
+    name: String,
+    installed: String,
+    constraint: Option<String>,
+    wanted: Option<String>,
+    latest: Option<String>,
+    outdated: bool,
+    error: Option<String>,
+}
+
+struct SyncStep {
+    repo: SmolStr,
+    action: SyncAction,
+}
+
+enum SyncAction {
+    Install {
+        package: ResolvedPackage,
+    },
+    Upgrade {
+        from: SmolStr,
+        package: ResolvedPackage,
+    },
+    Downgrade {
+        from: SmolStr,
+        package: ResolvedPackage,
+    },
+    /// A pinned source or `[patch]` path now resolves to another commit or content hash
+    Refresh {
+        from: SmolStr,
+        package: ResolvedPackage,
+    },
+    Reinstall {
+        package: ResolvedPackage,
+        reason: &'static str,
+    },
+    Repair {
+        package: InstalledPackage,
+        release: Option<GitHubRelease>,
+    },
+    /// Files in `patches/` changed, the locked files are installed and patched again
+    Repatch {
+        package: InstalledPackage,
+    },
+    Relock {
+        version: SmolStr,
+        constraint: Option<SmolStr>,
+        source: Option<PackageSource>,
+    },
+    Remove {
+        version: SmolStr,
+    },
+}
+
+enum SyncPrepared {
+    Package(PreparedPackage),
+    Locked {
+        package: InstalledPackage,
+        temp_dir: PathBuf,
+    },
+}
+
+impl SyncStep {
+    fn new(repo: &str, action: SyncAction) -> Self {
+        Self {
+            repo: repo.into(),
+            action,
+        }
+    }
+
+    fn needs_download(&self) -> bool {
+        !matches!(
+            self.action,
+            SyncAction::Remove { .. } | SyncAction::Relock { .. }
+        )
+    }
+
+    fn describe(&self) -> String {
+        match &self.action {
+            SyncAction::Install { package } => {
+                let via = if package.direct { "" } else { " (dependency)" };
+                format!(
+                    "+ install    {} {}{}",
+                    self.repo,
+                    display_version(&package.version),
+                    via
+                )
+            }
+            SyncAction::Upgrade { from, package } => {
+                format!("^ upgrade    {} {} -> {}", self.repo, from, package.version)
+            }
+            SyncAction::Downgrade { from, package } => {
+                format!("v downgrade  {} {} -> {}", self.repo, from, package.version)
+            }
+            SyncAction::Refresh { from, package } => format!(
+                "^ refresh    {} {} -> {} ({})",
+                self.repo,
+                display_version(from),
+                display_version(&package.version),
+                match (&package.patch, &package.source) {
+                    (Some(path), _) => format!("patch {}", path),
+                    (None, Some(source)) => source.to_string(),
+                    (None, None) => "GitHub release".to_string(),
+                }
+            ),
+            SyncAction::Reinstall { package, reason } => {
+                format!(
+                    "~ reinstall  {} {} ({})",
+                    self.repo,
+                    display_version(&package.version),
+                    reason
+                )
+            }
+            SyncAction::Repair { package, .. } => {
+                format!(
+                    "! repair     {} {} (files missing or modified)",
+                    self.repo,
+                    display_version(&package.version)
+                )
+            }
+            SyncAction::Repatch { package } => {
+                format!(
+                    "~ repatch    {} {} (patches changed)",
+                    self.repo,
+                    display_version(&package.version)
+                )
+            }
+            SyncAction::Relock {
+                version,
+                constraint,
+                source,
+            } => {
+                let now = match (source, constraint) {
+                    (Some(source), _) => format!("source now {}", source),
+                    (None, Some(constraint)) => format!("constraint now \"{}\"", constraint),
+                    (None, None) => "no constraint".to_string(),
+                };
+                format!(
+                    "= relock     {} {} ({})",
+                    self.repo,
+                    display_version(version),
+                    now
+                )
+            }
+            SyncAction::Remove { version } => {
+                format!("- remove     {} {}", self.repo, display_version(version))
+            }
+        }
+    }
+}
+
+fn release_notes_between(
+    releases: &[GitHubRelease],
+    from: &str,
+    to: &str,
+) -> Vec<(String, Option<String>)> {
+    let (Ok(from), Ok(to)) = (Version::parse(from), Version::parse(to)) else {
+        return Vec::new();
+    };
+
+    let mut notes: Vec<(Version, &GitHubRelease)> = releases
+        .iter()
+        .filter_map(|release| Version::parse(&release.tag_name).ok().map(|v| (v, release)))
+        .filter(|(version, release)| {
+            *version > from && *version <= to && (!release.prerelease || *version == to)
+        })
+        .collect();
+    notes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    notes
+        .into_iter()
+        .map(|(_, release)| (release.tag_name.clone(), release.body.clone()))
+        .collect()
+}
+
+/// Packages nothing else depends on, plus every package listed in opencli.toml
+fn top_level_packages(lock: &PackageLock) -> Vec<&str> {
+    let required: HashSet<&str> = lock
+        .list_packages()
+        .into_iter()
+        .flat_map(|(_, package)| package.dependencies.iter().flatten())
+        .map(|dependency| dependency.name.as_str())
+        .collect();
+
+    let mut roots: Vec<&str> = lock
+        .list_packages()
+        .into_iter()
+        .filter(|(repo, package)| package.constraint.is_some() || !required.contains(repo))
+        .map(|(repo, _)| repo)
+        .collect();
+    roots.sort();
+    roots
+}
+
+fn print_tree_node<'a>(
+    lock: &'a PackageLock,
+    repo: &'a str,
+    prefix: &str,
+    branch: &str,
+    path: &mut Vec<&'a str>,
+    printed: &mut HashSet<&'a str>,
+) {
+    let Some(package) = lock.get_package(repo) else {
+        println!("{}{}{} (missing from opencli.lock)", prefix, branch, repo);
+        return;
+    };
+
+    let target = match &package.target {
+        Some(PackageTarget::Components) => " (components)",
+        Some(PackageTarget::Plugins) => " (plugins)",
+        None => "",
+    };
+
+    let dependencies: Vec<&str> = package
+        .dependencies
+        .iter()
+        .flatten()
+        .map(|dependency| dependency.name.as_str())
+        .collect();
+    let version = display_version(&package.version);
+
+    if path.contains(&repo) {
+        println!("{}{}{} {}{} (cycle)", prefix, branch, repo, version, target);
+        return;
+    }
+    if !dependencies.is_empty() && !printed.insert(repo) {
+        println!("{}{}{} {}{} (*)", prefix, branch, repo, version, target);
+        return;
+    }
+
+    println!("{}{}{} {}{}", prefix, branch, repo, version, target);
+
+    let child_prefix = match branch {
+        "" => prefix.to_string(),
+        "└── " => format!("{}    ", prefix),
+        _ => format!("{}│   ", prefix),
+    };
+
+    path.push(repo);
+    for (index, dependency) in dependencies.iter().enumerate() {
+        let branch = if index + 1 == dependencies.len() {
+            "└── "
+        } else {
+            "├── "
+        };
+        print_tree_node(lock, dependency, &child_prefix, branch, path, printed);
+    }
+    path.pop();
+}
+
+/// Every path from a top-level package down to `repo`
+fn dependency_chains<'a>(lock: &'a PackageLock, repo: &'a str) -> Vec<Vec<&'a str>> {
+    let mut chains = Vec::new();
+
+    for root in top_level_packages(lock) {
+        let mut path = Vec::new();
+        collect_chains(lock, root, repo, &mut path, &mut chains);
+    }
+
+    chains
+}
+
+fn collect_chains<'a>(
+    lock: &'a PackageLock,
+    current: &'a str,
+    wanted: &str,
+    path: &mut Vec<&'a str>,
+    chains: &mut Vec<Vec<&'a str>>,
+) {
+    if path.contains(&current) {
+        return;
+    }
+
+    path.push(current);
+    if current == wanted {
+        chains.push(path.clone());
+    } else if let Some(package) = lock.get_package(current) {
+        for dependency in package.dependencies.iter().flatten() {
+            collect_chains(lock, &dependency.name, wanted, path, chains);
+        }
+    }
+    path.pop();
+}
+
+/// Matches an installed path by full path, path suffix or file name
+fn file_matches(installed: &str, query: &str) -> bool {
+    let installed = installed.replace('\\', "/");
+    let query = query.replace('\\', "/");
+    let query = query.trim_start_matches("./");
+
+    installed == query
+        || installed.ends_with(&format!("/{}", query))
+        || Path::new(&installed)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|name| name.eq_ignore_ascii_case(query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(temp_dir: &str, includes: &[&str]) -> Vec<String> {
+        let temp_dir = Path::new(temp_dir);
+        let includes: Vec<PathBuf> = includes.iter().map(|i| temp_dir.join(i)).collect();
+        include_layout(&includes, temp_dir)
+            .into_iter()
+            .map(|(_, relative)| relative.to_string_lossy().replace('\\', "/"))
+            .collect()
+    }
+
+    #[test]
+    fn includes_keep_their_path_below_the_include_folder() {
+        assert_eq!(
+            layout(
+                "/tmp/pkg",
+                &[
+                    "pkg-1.0/include/a.inc",
+                    "pkg-1.0/include/sub/b.inc",
+                    "pkg-1.0/qawno/Include/c.inc",
+                ]
+            ),
+            ["a.inc", "sub/b.inc", "c.inc"]
+        );
+    }
+
+    #[test]
+    fn includes_without_an_include_folder_drop_their_shared_prefix() {
+        assert_eq!(
+            layout(
+                "/tmp/pkg",
+                &[
+                    "YSI-Includes-5.10/YSI_Coding/y_hooks.inc",
+                    "YSI-Includes-5.10/YSI_Data/y_iterate.inc",
+                ]
+            ),
+            ["YSI_Coding/y_hooks.inc", "YSI_Data/y_iterate.inc"]
+        );
+
+        // Includes under an include folder don't count towards the shared prefix
+        assert_eq!(
+            layout(
+                "/tmp/pkg",
+                &["lib/include/a.inc", "lib/extra/b.inc", "lib/more/c.inc"]
+            ),
+            ["a.inc", "extra/b.inc", "more/c.inc"]
+        );
+    }
+
+    #[test]
+    fn a_single_include_installs_by_its_file_name() {
+        assert_eq!(
+            layout("/tmp/pkg", &["sscanf-2.13.8/sscanf2.inc"]),
+            ["sscanf2.inc"]
+        );
+        assert_eq!(layout("/tmp/pkg", &["sscanf2.inc"]), ["sscanf2.inc"]);
+    }
 }
@@ -0,0 +1,156 @@
+use crate::build::PackageTarget;
+use crate::package::downloader::PackageFiles;
+use crate::result::{OpenCliError, Result};
+use crate::utils::platform::Platform;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub const MANIFEST_FILE: &str = "opencli-package.toml";
+
+/// Declares how a package is laid out, so the installer need not guess
+///
+/// Patterns are globs relative to the directory holding the manifest (or the
+/// archive root for a manifest committed to the repository), matched with `/`
+/// separators; `*` does not cross directories, `**` does.
+///
+/// ```toml
+/// min_compiler = "v3.10.11"
+/// includes = ["qawno/include/*.inc"]
+/// components = ["components/*"]
+/// root = ["log-core.*"]
+///
+/// [platforms.linux]
+/// plugins = ["plugins/*.so"]
+///
+/// [platforms.windows]
+/// plugins = ["plugins/*.dll"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PackageManifest {
+    #[serde(default)]
+    pub min_compiler: Option<SmolStr>,
+    #[serde(default)]
+    pub includes: Vec<String>,
+    #[serde(flatten)]
+    pub binaries: ManifestBinaries,
+    /// Binaries for one OS (`windows`, `linux`, `macos`), added to the common lists
+    #[serde(default)]
+    pub platforms: HashMap<Platform, ManifestBinaries>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ManifestBinaries {
+    #[serde(default)]
+    pub components: Vec<String>,
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    /// Files copied next to the server executable
+    #[serde(default)]
+    pub root: Vec<String>,
+}
+
+impl PackageManifest {
+    pub fn parse(content: &str, source: &str) -> Result<Self> {
+        toml::from_str(content)
+            .map_err(|e| OpenCliError::Config(format!("Invalid {}: {}", source, e).into()))
+    }
+
+    /** Picks the package files declared by the manifest
+     *
+     * # Arguments
+     * * `files` - Every downloaded file with its path inside the package
+     * * `base` - Directory of the manifest inside the package (`""` for the root)
+     * * `target` - Restricts binaries to components or legacy plugins when set
+     * * `platforms` - Platforms whose `[platforms.*]` lists are added, every one when empty
+     */
+    pub fn select(
+        &self,
+        files: &[(PathBuf, String)],
+        base: &str,
+        target: Option<&PackageTarget>,
+        platforms: &[Platform],
+    ) -> Result<PackageFiles> {
+        let selected: Vec<&ManifestBinaries> = self
+            .platforms
+            .iter()
+            .filter(|(platform, _)| platforms.is_empty() || platforms.contains(platform))
+            .map(|(_, binaries)| binaries)
+            .collect();
+
+        let includes = build_globs(self.includes.iter())?;
+        let components = build_globs(
+            self.binaries
+                .components
+                .iter()
+                .chain(selected.iter().flat_map(|b| &b.components)),
+        )?;
+        let plugins = build_globs(
+            self.binaries
+                .plugins
+                .iter()
+                .chain(selected.iter().flat_map(|b| &b.plugins)),
+        )?;
+        let root = build_globs(
+            self.binaries
+                .root
+                .iter()
+                .chain(selected.iter().flat_map(|b| &b.root)),
+        )?;
+
+        let mut package_files = PackageFiles::default();
+
+        for (path, package_path) in files {
+            let package_path = package_path.replace('\\', "/");
+            let relative = if base.is_empty() {
+                package_path.as_str()
+            } else {
+                match package_path.strip_prefix(base) {
+                    Some(relative) => relative.trim_start_matches('/'),
+                    None => continue,
+                }
+            };
+
+            if includes.is_match(relative) {
+                package_files.includes.push(path.clone());
+            } else if root.is_match(relative) {
+                package_files.root_binaries.push(path.clone());
+            } else if components.is_match(relative) && target != Some(&PackageTarget::Plugins) {
+                package_files.component_binaries.push(path.clone());
+            } else if plugins.is_match(relative) && target != Some(&PackageTarget::Components) {
+                package_files.plugin_binaries.push(path.clone());
+            }
+        }
+
+        package_files.includes.sort();
+        package_files.root_binaries.sort();
+        package_files.component_binaries.sort();
+        package_files.plugin_binaries.sort();
+        package_files.from_manifest = true;
+        package_files.min_compiler = self.min_compiler.clone();
+
+        Ok(package_files)
+    }
+}
+
+fn build_globs<'a>(patterns: impl Iterator<Item = &'a String>) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern.trim_start_matches("./"))
+            .literal_separator(true)
+            .build()
+            .map_err(|e| {
+                OpenCliError::Config(
+                    format!("Invalid pattern {:?} in {}: {}", pattern, MANIFEST_FILE, e).into(),
+                )
+            })?;
+        builder.add(glob);
+    }
+
+    builder.build().map_err(|e| {
+        OpenCliError::Config(format!("Invalid patterns in {}: {}", MANIFEST_FILE, e).into())
+    })
+}
@@ -2,12 +2,20 @@ pub mod config_manager;
 pub mod downloader;
 pub mod lock;
 pub mod manager;
+pub mod manifest;
+pub mod resolver;
+pub mod source;
 pub mod version;
 pub mod workspace;
 
 pub use config_manager::ConfigManager;
 pub use downloader::PackageDownloader;
-pub use lock::{InstalledPackage, PackageLock};
+pub use lock::{
+    AppliedPatch, InstalledPackage, LockedAsset, LockedCompiler, LockedFile, PackageDependency,
+    PackageLock,
+};
 pub use manager::PackageManager;
+pub use resolver::{DependencyResolver, ResolvedPackage};
+pub use source::{GitSource, PackageSource};
 pub use version::VersionConstraint;
 pub use workspace::WorkspaceDetector;
@@ -0,0 +1,412 @@
+use crate::build::PackageTarget;
+use crate::package::downloader::{GitHubRelease, PackageDownloader};
+use crate::package::lock::{PackageDependency, PackageLock};
+use crate::package::source::{display_version, PackageSource};
+use crate::package::version::{Version, VersionConstraint};
+use crate::result::{OpenCliError, Result};
+use crate::utils::platform::Platform;
+use smol_str::SmolStr;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+// Each round re-resolves with the requirements learnt in the previous one
+const MAX_ROUNDS: usize = 16;
+
+/// A package selected by the resolver
+#[derive(Debug, Clone)]
+pub struct ResolvedPackage {
+    pub version: SmolStr,
+    /// Release to install; `None` when the locked version was kept or the source is pinned
+    pub release: Option<GitHubRelease>,
+    /// Constraint from opencli.toml, or from the first dependent for transitive packages
+    pub constraint: SmolStr,
+    pub target: Option<PackageTarget>,
+    /// Listed in opencli.toml rather than pulled in by another package
+    pub direct: bool,
+    pub dependencies: Vec<PackageDependency>,
+    /// Where the package comes from; pinned sources use the commit or content hash as `version`
+    pub source: Option<PackageSource>,
+    /// Local path from `[patch]` that replaces `source`, `version` is then its content hash
+    pub patch: Option<SmolStr>,
+    /// Platforms whose assets are installed, filled in by the package manager
+    pub platforms: Vec<Platform>,
+    /// `assets` and `files.exclude` globs, filled in by the package manager
+    pub asset_patterns: Vec<SmolStr>,
+    pub excluded_files: Vec<SmolStr>,
+}
+
+#[derive(Debug, Clone)]
+struct Requirement {
+    constraint: SmolStr,
+    target: Option<PackageTarget>,
+    source: Option<PackageSource>,
+    /// Packages between opencli.toml and the requirement, e.g. `["a/b v1.0.0"]`
+    chain: Vec<String>,
+}
+
+/** Builds the full package graph and picks one version per package
+ *
+ * Locked versions are kept while they satisfy every requirement, so resolving
+ * an unchanged project never touches the network for releases.
+ */
+pub struct DependencyResolver<'a> {
+    downloader: &'a PackageDownloader,
+    lock: &'a PackageLock,
+    unlocked: HashSet<SmolStr>,
+    unlock_all: bool,
+    patches: HashMap<SmolStr, SmolStr>,
+    releases: HashMap<SmolStr, Vec<GitHubRelease>>,
+    manifests: HashMap<(SmolStr, SmolStr), Vec<PackageDependency>>,
+}
+
+impl<'a> DependencyResolver<'a> {
+    pub fn new(downloader: &'a PackageDownloader, lock: &'a PackageLock) -> Self {
+        Self {
+            downloader,
+            lock,
+            unlocked: HashSet::new(),
+            unlock_all: false,
+            patches: HashMap::new(),
+            releases: HashMap::new(),
+            manifests: HashMap::new(),
+        }
+    }
+
+    /// Ignores the locked version of `repo` and picks the newest allowed release
+    pub fn unlock(&mut self, repo: &str) {
+        self.unlocked.insert(repo.into());
+    }
+
+    /// Ignores every locked version
+    pub fn unlock_all(&mut self) {
+        self.unlock_all = true;
+    }
+
+    /// Takes `repo` from a local path instead of its source, whatever requires it
+    pub fn patch(&mut self, repo: &str, path: &str) {
+        self.patches.insert(repo.into(), path.into());
+    }
+
+    /** Resolves `roots` (the `[packages]` of opencli.toml) and everything they depend on
+     *
+     * # Errors
+     * - `OpenCliError::Config` naming each requirement and the chain of packages behind it
+     *   when no release satisfies them all
+     */
+    pub async fn resolve(
+        &mut self,
+        roots: &[PackageDependency],
+    ) -> Result<BTreeMap<SmolStr, ResolvedPackage>> {
+        let mut hints: HashMap<SmolStr, Vec<Requirement>> = HashMap::new();
+        let mut conflict = None;
+        let mut previous: Option<Vec<(SmolStr, SmolStr)>> = None;
+
+        for _ in 0..MAX_ROUNDS {
+            let (selected, requirements) = self.resolve_round(roots, &hints).await?;
+            let versions: Vec<(SmolStr, SmolStr)> = selected
+                .iter()
+                .map(|(repo, package)| (repo.clone(), package.version.clone()))
+                .collect();
+
+            let unsatisfied: Vec<&SmolStr> = selected
+                .iter()
+                .filter(|(repo, package)| {
+                    // Patched packages and pinned sources ignore version constraints
+                    package.patch.is_none()
+                        && package
+                            .source
+                            .as_ref()
+                            .is_none_or(PackageSource::is_versioned)
+                        && !requirements[*repo]
+                            .iter()
+                            .all(|r| satisfies(&package.version, &r.constraint))
+                })
+                .map(|(repo, _)| repo)
+                .collect();
+
+            let Some(repo) = unsatisfied.first() else {
+                return Ok(selected);
+            };
+
+            conflict = Some(conflict_error(repo, &requirements[*repo]));
+            if previous.as_ref() == Some(&versions) {
+                // Another round would pick the same versions again
+                break;
+            }
+
+            previous = Some(versions);
+            hints = requirements;
+        }
+
+        Err(conflict.unwrap_or_else(|| {
+            OpenCliError::Config("Package dependencies did not converge".into())
+        }))
+    }
+
+    async fn resolve_round(
+        &mut self,
+        roots: &[PackageDependency],
+        hints: &HashMap<SmolStr, Vec<Requirement>>,
+    ) -> Result<(
+        BTreeMap<SmolStr, ResolvedPackage>,
+        HashMap<SmolStr, Vec<Requirement>>,
+    )> {
+        let mut requirements: HashMap<SmolStr, Vec<Requirement>> = HashMap::new();
+        let mut selected = BTreeMap::new();
+        let mut queue = VecDeque::new();
+
+        for root in roots {
+            requirements
+                .entry(root.name.clone())
+                .or_default()
+                .push(Requirement {
+                    constraint: root.version.clone(),
+                    target: root.target.clone(),
+                    source: root.source.clone(),
+                    chain: Vec::new(),
+                });
+            queue.push_back(root.name.clone());
+        }
+
+        while let Some(repo) = queue.pop_front() {
+            if selected.contains_key(&repo) {
+                continue;
+            }
+
+            let current = requirements[&repo].clone();
+            let source = current.iter().find_map(|r| r.source.clone());
+            let patch = self.patches.get(&repo).cloned();
+            let patched = patch.clone().map(|path| PackageSource::Path { path });
+
+            let (version, release) = match &patched {
+                Some(patched) => self.select_pinned(&repo, patched).await?,
+                None => {
+                    self.select(&repo, &current, hints.get(&repo).map(Vec::as_slice))
+                        .await?
+                }
+            };
+            let dependencies = self
+                .dependencies_of(&repo, &version, patched.as_ref().or(source.as_ref()))
+                .await?;
+
+            let mut chain = current[0].chain.clone();
+            chain.push(format!("{} {}", repo, display_version(&version)));
+
+            for dependency in &dependencies {
+                // A package must not make the installer read this machine's files or repositories
+                if let Some(source) = dependency.source.as_ref().filter(|s| s.is_local()) {
+                    return Err(OpenCliError::Config(
+                        format!(
+                            "opencli.toml -> {} asks for {} from {}, but local paths and file:// remotes are only allowed in the project's opencli.toml",
+                            chain.join(" -> "),
+                            dependency.name,
+                            source
+                        )
+                        .into(),
+                    ));
+                }
+
+                requirements
+                    .entry(dependency.name.clone())
+                    .or_default()
+                    .push(Requirement {
+                        constraint: dependency.version.clone(),
+                        target: dependency.target.clone(),
+                        source: dependency.source.clone(),
+                        chain: chain.clone(),
+                    });
+                queue.push_back(dependency.name.clone());
+            }
+
+            selected.insert(
+                repo.clone(),
+                ResolvedPackage {
+                    version,
+                    release,
+                    constraint: current[0].constraint.clone(),
+                    target: current[0].target.clone(),
+                    direct: current[0].chain.is_empty(),
+                    dependencies,
+                    source,
+                    patch,
+                    platforms: Vec::new(),
+                    asset_patterns: Vec::new(),
+                    excluded_files: Vec::new(),
+                },
+            );
+        }
+
+        Ok((selected, requirements))
+    }
+
+    async fn select(
+        &mut self,
+        repo: &SmolStr,
+        requirements: &[Requirement],
+        hints: Option<&[Requirement]>,
+    ) -> Result<(SmolStr, Option<GitHubRelease>)> {
+        let source = requirements.iter().find_map(|r| r.source.as_ref());
+        if let Some(source) = source {
+            if requirements
+                .iter()
+                .any(|r| r.source.as_ref().is_some_and(|other| other != source))
+            {
+                return Err(conflict_error(repo, requirements));
+            }
+            if !source.is_versioned() {
+                return self.select_pinned(repo, source).await;
+            }
+        }
+
+        let hinted: Vec<&Requirement> = requirements.iter().chain(hints.unwrap_or(&[])).collect();
+
+        if !self.unlock_all && !self.unlocked.contains(repo) {
+            if let Some(locked) = self.lock.get_package(repo) {
+                if locked.patch.is_none()
+                    && locked.source.as_ref() == source
+                    && hinted
+                        .iter()
+                        .all(|r| satisfies(&locked.version, &r.constraint))
+                {
+                    return Ok((locked.version.clone(), None));
+                }
+            }
+        }
+
+        let releases = self.releases(repo, source).await?;
+
+        let release = newest_satisfying(releases, hinted.iter().copied())
+            .or_else(|| newest_satisfying(releases, requirements.iter()))
+            .cloned()
+            .ok_or_else(|| conflict_error(repo, requirements))?;
+
+        Ok((release.tag_name.as_str().into(), Some(release)))
+    }
+
+    async fn select_pinned(
+        &mut self,
+        repo: &SmolStr,
+        source: &PackageSource,
+    ) -> Result<(SmolStr, Option<GitHubRelease>)> {
+        if !self.unlock_all && !self.unlocked.contains(repo) {
+            if let Some(locked) = self.lock.get_package(repo).filter(|p| p.patch.is_none()) {
+                let keep = match (source, &locked.source) {
+                    // A rev that matches the locked commit needs no lookup even if the spec changed
+                    (PackageSource::Git(git), Some(PackageSource::Git(locked_git))) => {
+                        (git == locked_git || git.rev.is_some()) && git.accepts(&locked.version)
+                    }
+                    // Local files are hashed on every run to notice edits
+                    (PackageSource::Path { .. }, _) => false,
+                    (_, locked_source) => locked_source.as_ref() == Some(source),
+                };
+                if keep {
+                    return Ok((locked.version.clone(), None));
+                }
+            }
+        }
+
+        let version = self.downloader.resolve_pinned(source).await?;
+        Ok((version, None))
+    }
+
+    async fn releases(
+        &mut self,
+        repo: &SmolStr,
+        source: Option<&PackageSource>,
+    ) -> Result<&[GitHubRelease]> {
+        if !self.releases.contains_key(repo) {
+            let releases = self.downloader.releases_for(repo, source).await?;
+            self.releases.insert(repo.clone(), releases);
+        }
+
+        Ok(&self.releases[repo])
+    }
+
+    async fn dependencies_of(
+        &mut self,
+        repo: &SmolStr,
+        version: &SmolStr,
+        source: Option<&PackageSource>,
+    ) -> Result<Vec<PackageDependency>> {
+        let key = (repo.clone(), version.clone());
+        if let Some(dependencies) = self.manifests.get(&key) {
+            return Ok(dependencies.clone());
+        }
+
+        let recorded = self
+            .lock
+            .get_package(repo)
+            .filter(|locked| locked.version == *version)
+            .and_then(|locked| locked.dependencies.clone());
+
+        let dependencies = match recorded {
+            Some(dependencies) => dependencies,
+            None => match source {
+                Some(source @ PackageSource::Path { .. }) => {
+                    self.downloader.local_dependencies(repo, source).await?
+                }
+                _ => match source.map_or(Some(repo.as_str()), PackageSource::github_repo) {
+                    Some(origin) => self.downloader.fetch_dependencies(origin, version).await?,
+                    // Dependencies are only read from GitHub repositories and local folders
+                    None => Vec::new(),
+                },
+            },
+        };
+
+        self.manifests.insert(key, dependencies.clone());
+        Ok(dependencies)
+    }
+}
+
+fn satisfies(version: &str, constraint: &str) -> bool {
+    if version == constraint {
+        return true;
+    }
+
+    match (
+        VersionConstraint::parse(constraint),
+        Version::parse(version),
+    ) {
+        (Ok(constraint), Ok(version)) => constraint.matches(&version),
+        _ => false,
+    }
+}
+
+fn newest_satisfying<'r>(
+    releases: &'r [GitHubRelease],
+    requirements: impl Iterator<Item = &'r Requirement> + Clone,
+) -> Option<&'r GitHubRelease> {
+    releases
+        .iter()
+        .filter_map(|release| Version::parse(&release.tag_name).ok().map(|v| (v, release)))
+        .filter(|(_, release)| {
+            requirements
+                .clone()
+                .all(|r| satisfies(&release.tag_name, &r.constraint))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, release)| release)
+}
+
+fn conflict_error(repo: &str, requirements: &[Requirement]) -> OpenCliError {
+    let lines: Vec<String> = requirements
+        .iter()
+        .map(|r| {
+            let mut chain = vec!["opencli.toml".to_string()];
+            chain.extend(r.chain.iter().cloned());
+            let wanted = match &r.source {
+                Some(source) => source.to_string(),
+                None => format!("\"{}\"", r.constraint),
+            };
+            format!("{} required by {}", wanted, chain.join(" -> "))
+        })
+        .collect();
+
+    OpenCliError::Config(
+        format!(
+            "No version of {} satisfies all requirements:\n  - {}",
+            repo,
+            lines.join("\n  - ")
+        )
+        .into(),
+    )
+}
@@ -0,0 +1,328 @@
+use crate::package::downloader::{GitHubAsset, GitHubRelease};
+use crate::result::{OpenCliError, Result};
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+pub const GITLAB_HOST: &str = "https://gitlab.com";
+
+/** Where a package comes from when it is not a GitHub release
+ *
+ * ```toml
+ * [packages]
+ * "pawn-lang/YSI-Includes" = { git = "pawn-lang/YSI-Includes", branch = "5.x" }
+ * "team/lib" = { git = "https://git.example.com/team/lib.git", tag = "v1.0.0" }
+ * "group/project" = { gitlab = "group/project", version = "^1.0" }
+ * "team/internal" = { gitea = "team/internal", host = "https://gitea.example.com", version = "^2.0" }
+ * "vendor/lib" = { url = "https://example.com/lib-1.0.zip", sha256 = "9f86d08..." }
+ * "local/lib" = { path = "../lib" }
+ * ```
+ *
+ * GitLab and Gitea releases are picked by version constraint like GitHub
+ * releases. The other sources are pinned: the lock records the resolved
+ * commit, archive hash or content hash and `version` is ignored.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PackageSource {
+    Git(GitSource),
+    GitLab {
+        gitlab: SmolStr,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<SmolStr>,
+    },
+    Gitea {
+        gitea: SmolStr,
+        host: SmolStr,
+    },
+    Url {
+        url: SmolStr,
+        sha256: SmolStr,
+    },
+    Path {
+        path: SmolStr,
+    },
+}
+
+/// A branch, tag or commit of a GitHub repository or any git remote
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitSource {
+    /// GitHub repository in `owner/repo` form, or a remote URL for the `git` CLI
+    pub git: SmolStr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<SmolStr>,
+}
+
+impl PackageSource {
+    pub fn validate(&self, name: &str) -> Result<()> {
+        let invalid = |message: String| {
+            Err(OpenCliError::Config(
+                format!("Package {}: {}", name, message).into(),
+            ))
+        };
+
+        match self {
+            PackageSource::Git(git) => git.validate(name),
+            PackageSource::GitLab { gitlab, host } => {
+                if gitlab.trim_matches('/').is_empty() {
+                    return invalid("gitlab must name a project, e.g. \"group/project\"".into());
+                }
+                match host {
+                    Some(host) if !is_http_url(host) => {
+                        invalid(format!("host \"{}\" must be an http(s) URL", host))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            PackageSource::Gitea { gitea, host } => {
+                if !is_owner_repo(gitea) {
+                    return invalid(format!("gitea = \"{}\", expected \"owner/repo\"", gitea));
+                }
+                if !is_http_url(host) {
+                    return invalid(format!("host \"{}\" must be an http(s) URL", host));
+                }
+                Ok(())
+            }
+            PackageSource::Url { url, sha256 } => {
+                if !is_http_url(url) {
+                    return invalid(format!("url \"{}\" must be an http(s) URL", url));
+                }
+                if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return invalid("sha256 must be 64 hex characters".into());
+                }
+                Ok(())
+            }
+            PackageSource::Path { path } => {
+                if path.is_empty() {
+                    return invalid("path must not be empty".into());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Releases picked by version constraint; everything else is pinned by the lock
+    pub fn is_versioned(&self) -> bool {
+        matches!(
+            self,
+            PackageSource::GitLab { .. } | PackageSource::Gitea { .. }
+        )
+    }
+
+    /// The GitHub repository behind the source, for release metadata and raw files
+    pub fn github_repo(&self) -> Option<&str> {
+        match self {
+            PackageSource::Git(git) if git.is_github() => Some(&git.git),
+            _ => None,
+        }
+    }
+
+    /// Whether the lock can reproduce the package from its recorded asset URLs
+    pub fn has_assets(&self) -> bool {
+        match self {
+            PackageSource::Git(git) => git.is_github(),
+            PackageSource::Path { .. } => false,
+            _ => true,
+        }
+    }
+
+    /// Reads from this machine, which only the project's own opencli.toml may ask for
+    pub fn is_local(&self) -> bool {
+        match self {
+            PackageSource::Path { .. } => true,
+            PackageSource::Git(git) => git.git.starts_with("file://"),
+            _ => false,
+        }
+    }
+
+    /// Local path, relative paths are taken from the project directory
+    pub fn local_path(&self) -> Option<PathBuf> {
+        match self {
+            PackageSource::Path { path } => {
+                let path = Path::new(path.as_str());
+                Some(if path.is_relative() {
+                    std::env::current_dir()
+                        .map(|dir| dir.join(path))
+                        .unwrap_or_else(|_| path.to_path_buf())
+                } else {
+                    path.to_path_buf()
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PackageSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageSource::Git(git) => write!(f, "{}", git),
+            PackageSource::GitLab { gitlab, host } => {
+                write!(
+                    f,
+                    "GitLab {} on {}",
+                    gitlab,
+                    host.as_deref().unwrap_or(GITLAB_HOST)
+                )
+            }
+            PackageSource::Gitea { gitea, host } => write!(f, "Gitea {} on {}", gitea, host),
+            PackageSource::Url { url, .. } => write!(f, "{}", url),
+            PackageSource::Path { path } => write!(f, "path {}", path),
+        }
+    }
+}
+
+impl GitSource {
+    pub fn validate(&self, name: &str) -> Result<()> {
+        let refs = [&self.rev, &self.branch, &self.tag]
+            .iter()
+            .filter(|r| r.is_some())
+            .count();
+        if refs > 1 {
+            return Err(OpenCliError::Config(
+                format!("Package {} may set only one of rev, branch or tag", name).into(),
+            ));
+        }
+
+        if !self.is_github() && !is_git_url(&self.git) {
+            return Err(OpenCliError::Config(
+                format!(
+                    "Package {} has git = \"{}\", expected \"owner/repo\" or a git URL",
+                    name, self.git
+                )
+                .into(),
+            ));
+        }
+
+        if !self.is_github() && self.rev.as_deref().is_some_and(|rev| !is_commit(rev)) {
+            return Err(OpenCliError::Config(
+                format!(
+                    "Package {} needs a full 40 character rev for a git URL",
+                    name
+                )
+                .into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// `owner/repo` on GitHub, as opposed to a remote fetched with the `git` CLI
+    pub fn is_github(&self) -> bool {
+        is_owner_repo(&self.git)
+    }
+
+    /// Ref passed to GitHub, `HEAD` for the default branch
+    pub fn reference(&self) -> &str {
+        self.rev
+            .as_deref()
+            .or(self.tag.as_deref())
+            .or(self.branch.as_deref())
+            .unwrap_or("HEAD")
+    }
+
+    /// Whether a locked commit still matches this source without resolving the ref again
+    pub fn accepts(&self, commit: &str) -> bool {
+        match &self.rev {
+            Some(rev) => commit.starts_with(rev.as_str()),
+            None => true,
+        }
+    }
+
+    /// Folder name of the checkout, the repository name without `.git`
+    pub fn repo_name(&self) -> &str {
+        let git = self.git.trim_end_matches('/');
+        let name = git.rsplit(['/', ':']).next().unwrap_or(git);
+        name.trim_end_matches(".git")
+    }
+
+    /// The source tarball of `commit`, shaped like a release with a single asset
+    pub fn release(&self, commit: &str) -> GitHubRelease {
+        if !self.is_github() {
+            return pinned_release(commit, Vec::new());
+        }
+
+        pinned_release(
+            commit,
+            vec![GitHubAsset {
+                name: format!("{}-{}.tar.gz", self.repo_name(), commit),
+                download_url: format!("https://codeload.github.com/{}/tar.gz/{}", self.git, commit),
+                size: 0,
+                digest: None,
+            }],
+        )
+    }
+}
+
+impl fmt::Display for GitSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(rev) = &self.rev {
+            write!(f, "{} rev {}", self.git, rev)
+        } else if let Some(tag) = &self.tag {
+            write!(f, "{} tag {}", self.git, tag)
+        } else if let Some(branch) = &self.branch {
+            write!(f, "{} branch {}", self.git, branch)
+        } else {
+            write!(f, "{} default branch", self.git)
+        }
+    }
+}
+
+/// A release-shaped wrapper for packages that are not installed from a release
+pub fn pinned_release(version: &str, assets: Vec<GitHubAsset>) -> GitHubRelease {
+    GitHubRelease {
+        tag_name: version.to_string(),
+        body: None,
+        prerelease: false,
+        assets,
+    }
+}
+
+pub fn is_commit(version: &str) -> bool {
+    version.len() == 40 && version.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Shortens commit versions to 7 characters for display, leaves tags alone
+pub fn display_version(version: &str) -> &str {
+    if is_commit(version) {
+        &version[..7]
+    } else {
+        version
+    }
+}
+
+fn is_owner_repo(repo: &str) -> bool {
+    repo.split_once('/').is_some_and(|(owner, repo)| {
+        !owner.is_empty() && !repo.is_empty() && !repo.contains('/') && !owner.contains(':')
+    })
+}
+
+/// Remotes handed to the `git` CLI; anything else could be read as an option or transport
+fn is_git_url(url: &str) -> bool {
+    if url.starts_with('-') {
+        return false;
+    }
+
+    if ["https://", "ssh://", "git://", "file://"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+    {
+        return true;
+    }
+
+    // scp-style `git@host:path`
+    url.split_once(':').is_some_and(|(host, path)| {
+        host.split_once('@')
+            .is_some_and(|(user, host)| !user.is_empty() && !host.is_empty() && !host.contains('/'))
+            && !path.is_empty()
+    })
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
@@ -177,6 +177,79 @@ impl SecurityManager {
 
         Ok(argon2_hash.to_string())
     }
+
+    /** Computes the plain SHA-256 digest of a file as lowercase hex
+     *
+     * # Purpose
+     * - Compares downloads against digests pinned in configuration
+     * - Unlike `hash_file`, the result is deterministic (no salt)
+     *
+     * # Arguments
+     * * `file_path` - Path to the file to digest
+     *
+     * # Returns
+     * - 64-character lowercase hex string
+     *
+     * # Example
+     * ```ignore
+     * let security = SecurityManager::new();
+     * let digest = security.sha256_file(Path::new("pawnc-3.10.11-linux.tar.gz")).await?;
+     * ```
+     */
+    pub async fn sha256_file(&self, file_path: &Path) -> Result<String> {
+        let content = fs::read(file_path).await?;
+        let mut hasher = Sha256::new();
+        hasher.update(&content);
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /** Compares two SHA-256 hex digests
+     *
+     * # Notes
+     * - Case-insensitive
+     * - Accepts an optional `sha256:` prefix on either side
+     */
+    pub fn digest_matches(expected: &str, actual: &str) -> bool {
+        fn normalize(digest: &str) -> String {
+            let digest = digest.trim();
+            digest
+                .strip_prefix("sha256:")
+                .unwrap_or(digest)
+                .to_ascii_lowercase()
+        }
+
+        normalize(expected) == normalize(actual)
+    }
+
+    /** Verifies a detached minisign signature over in-memory content
+     *
+     * # Arguments
+     * * `content` - Signed bytes
+     * * `signature` - Contents of the `.minisig` file
+     * * `public_key` - Either the bare base64 key or a full `minisign.pub` file
+     *
+     * # Returns
+     * - `Ok(())` if the signature is valid for the key
+     * - `Err(OpenCliError::Config)` if the key or signature cannot be decoded
+     *   or the signature does not match
+     */
+    pub fn verify_minisign(&self, content: &[u8], signature: &str, public_key: &str) -> Result<()> {
+        let public_key = public_key.trim();
+        let key = if public_key.lines().count() > 1 {
+            minisign_verify::PublicKey::decode(public_key)
+        } else {
+            minisign_verify::PublicKey::from_base64(public_key)
+        }
+        .map_err(|e| OpenCliError::Config(format!("Invalid minisign public key: {}", e).into()))?;
+
+        let signature = minisign_verify::Signature::decode(signature).map_err(|e| {
+            OpenCliError::Config(format!("Invalid minisign signature: {}", e).into())
+        })?;
+
+        key.verify(content, &signature, false).map_err(|e| {
+            OpenCliError::Config(format!("Signature verification failed: {}", e).into())
+        })
+    }
 }
 
 /*
@@ -0,0 +1,980 @@
+use crate::result::{OpenCliError, Result};
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+const MAX_LINK_TARGET_LEN: u64 = 4096;
+
+/// Numbers RAR staging directories so parallel extractions never share one
+static STAGING_COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy)]
+pub struct ArchiveLimits {
+    pub max_entries: usize,
+    pub max_entry_size: u64,
+    pub max_total_size: u64,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 20_000,
+            max_entry_size: 512 * 1024 * 1024,
+            max_total_size: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+    TarXz,
+    TarBz2,
+    SevenZip,
+    Rar,
+}
+
+impl ArchiveFormat {
+    pub fn detect(file_name: &str) -> Option<Self> {
+        let name = file_name.to_lowercase();
+
+        if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+            Some(ArchiveFormat::TarXz)
+        } else if name.ends_with(".tar.bz2") || name.ends_with(".tbz2") || name.ends_with(".tbz") {
+            Some(ArchiveFormat::TarBz2)
+        } else if name.ends_with(".7z") {
+            Some(ArchiveFormat::SevenZip)
+        } else if name.ends_with(".rar") {
+            Some(ArchiveFormat::Rar)
+        } else {
+            None
+        }
+    }
+
+    /// Maps a `method` value from compilers.toml to a format
+    pub fn from_method(method: &str) -> Option<Self> {
+        match method.to_lowercase().as_str() {
+            "zip" => Some(ArchiveFormat::Zip),
+            "tgz" | "tar.gz" => Some(ArchiveFormat::TarGz),
+            "txz" | "tar.xz" => Some(ArchiveFormat::TarXz),
+            "tbz2" | "tar.bz2" => Some(ArchiveFormat::TarBz2),
+            "7z" => Some(ArchiveFormat::SevenZip),
+            "rar" => Some(ArchiveFormat::Rar),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtractedFile {
+    pub path: PathBuf,
+    pub archive_path: String,
+}
+
+#[derive(Default)]
+pub struct ArchiveExtractor {
+    limits: ArchiveLimits,
+}
+
+enum EntryKind {
+    Directory,
+    File,
+    Link { target: String, hard: bool },
+    Other,
+}
+
+struct ExtractionState<'a> {
+    destination: &'a Path,
+    limits: ArchiveLimits,
+    entries: usize,
+    total_size: u64,
+    files: Vec<ExtractedFile>,
+    extracted: HashMap<PathBuf, PathBuf>,
+}
+
+impl ArchiveExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(limits: ArchiveLimits) -> Self {
+        Self { limits }
+    }
+
+    pub async fn extract(
+        &self,
+        archive_path: &Path,
+        destination: &Path,
+    ) -> Result<Vec<ExtractedFile>> {
+        let file_name = archive_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("");
+
+        match ArchiveFormat::detect(file_name) {
+            Some(format) => self.extract_as(format, archive_path, destination).await,
+            None => Err(OpenCliError::Process(
+                format!("Unsupported archive format: {}", file_name).into(),
+            )),
+        }
+    }
+
+    pub async fn extract_as(
+        &self,
+        format: ArchiveFormat,
+        archive_path: &Path,
+        destination: &Path,
+    ) -> Result<Vec<ExtractedFile>> {
+        match format {
+            ArchiveFormat::Zip => self.extract_zip(archive_path, destination).await,
+            ArchiveFormat::TarGz => self.extract_tar_gz(archive_path, destination).await,
+            ArchiveFormat::TarXz => {
+                let file = File::open(archive_path)?;
+                let decoder = lzma_rust2::XzReader::new(std::io::BufReader::new(file), true);
+                self.extract_tar(decoder, destination)
+            }
+            ArchiveFormat::TarBz2 => {
+                let file = File::open(archive_path)?;
+                self.extract_tar(bzip2::read::MultiBzDecoder::new(file), destination)
+            }
+            ArchiveFormat::SevenZip => self.extract_7z(archive_path, destination).await,
+            ArchiveFormat::Rar => self.extract_rar(archive_path, destination).await,
+        }
+    }
+
+    pub async fn extract_zip(
+        &self,
+        archive_path: &Path,
+        destination: &Path,
+    ) -> Result<Vec<ExtractedFile>> {
+        let file = File::open(archive_path)
+            .map_err(|e| OpenCliError::Process(format!("Failed to open zip file: {}", e).into()))?;
+
+        let mut archive = zip::ZipArchive::new(file).map_err(|e| {
+            OpenCliError::Process(format!("Failed to read zip archive: {}", e).into())
+        })?;
+
+        let mut state = ExtractionState::new(destination, self.limits)?;
+
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|e| {
+                OpenCliError::Process(format!("Failed to read zip entry: {}", e).into())
+            })?;
+
+            let name = entry.name().to_string();
+
+            let kind = if entry.is_dir() {
+                EntryKind::Directory
+            } else if entry.is_symlink() {
+                let mut target = String::new();
+                (&mut entry)
+                    .take(MAX_LINK_TARGET_LEN)
+                    .read_to_string(&mut target)
+                    .map_err(|e| {
+                        OpenCliError::Process(
+                            format!("Failed to read zip link {}: {}", name, e).into(),
+                        )
+                    })?;
+                EntryKind::Link {
+                    target,
+                    hard: false,
+                }
+            } else {
+                EntryKind::File
+            };
+
+            state.add_entry(&name, kind, &mut entry)?;
+        }
+
+        Ok(state.files)
+    }
+
+    pub async fn extract_tar_gz(
+        &self,
+        archive_path: &Path,
+        destination: &Path,
+    ) -> Result<Vec<ExtractedFile>> {
+        let file = File::open(archive_path)?;
+        self.extract_tar(GzDecoder::new(file), destination)
+    }
+
+    pub async fn extract_7z(
+        &self,
+        archive_path: &Path,
+        destination: &Path,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut reader =
+            sevenz_rust::SevenZReader::open(archive_path, sevenz_rust::Password::empty()).map_err(
+                |e| OpenCliError::Process(format!("Failed to read 7z archive: {}", e).into()),
+            )?;
+
+        let mut state = ExtractionState::new(destination, self.limits)?;
+        let mut failure = None;
+
+        reader
+            .for_each_entries(|entry, data| {
+                if entry.is_anti_item {
+                    return Ok(true);
+                }
+
+                let kind = if entry.is_directory {
+                    EntryKind::Directory
+                } else {
+                    EntryKind::File
+                };
+
+                match state.add_entry(entry.name(), kind, data) {
+                    Ok(()) => Ok(true),
+                    Err(e) => {
+                        failure = Some(e);
+                        Ok(false)
+                    }
+                }
+            })
+            .map_err(|e| {
+                OpenCliError::Process(format!("Failed to extract 7z archive: {}", e).into())
+            })?;
+
+        if let Some(e) = failure {
+            return Err(e);
+        }
+
+        Ok(state.files)
+    }
+
+    // No pure Rust RAR decoder is available, so RAR goes through an external
+    // tool into a staging directory and is then re-imported with the same checks.
+    // The tool's listing is checked against the limits first so a RAR bomb is
+    // refused before anything is unpacked.
+    pub async fn extract_rar(
+        &self,
+        archive_path: &Path,
+        destination: &Path,
+    ) -> Result<Vec<ExtractedFile>> {
+        let tool = rar_tool(archive_path)?;
+        self.check_rar_listing(tool, archive_path).await?;
+
+        let staging = std::env::temp_dir().join("opencli").join(format!(
+            "rar-staging-{}-{}",
+            std::process::id(),
+            STAGING_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        std::fs::create_dir_all(&staging)?;
+
+        let result = async {
+            self.run_rar_tool(tool, archive_path, &staging).await?;
+            self.import_directory(&staging, destination)
+        }
+        .await;
+
+        if let Err(e) = std::fs::remove_dir_all(&staging) {
+            log::warn!("Failed to remove RAR staging directory: {}", e);
+        }
+
+        result
+    }
+
+    async fn check_rar_listing(&self, tool: &str, archive_path: &Path) -> Result<()> {
+        let archive = archive_path.to_string_lossy().to_string();
+        let args: Vec<String> = match tool {
+            "unrar" => vec!["lt".into(), "-y".into(), archive],
+            "bsdtar" => vec!["-tvf".into(), archive],
+            _ => vec!["l".into(), "-slt".into(), "-y".into(), archive],
+        };
+
+        let output = run_tool(tool, &args, "list", archive_path).await?;
+        let sizes = parse_rar_listing(tool, &String::from_utf8_lossy(&output));
+        self.check_listed_sizes(&sizes)
+            .map_err(|e| OpenCliError::Process(format!("{}: {}", archive_path.display(), e).into()))
+    }
+
+    /// Applies the extraction limits to the entry sizes an archive listing reports
+    fn check_listed_sizes(&self, sizes: &[u64]) -> std::result::Result<(), String> {
+        if sizes.len() > self.limits.max_entries {
+            return Err(format!(
+                "archive has more than {} entries, refusing to extract",
+                self.limits.max_entries
+            ));
+        }
+
+        let mut total_size: u64 = 0;
+        for &size in sizes {
+            total_size = total_size.saturating_add(size);
+            if size > self.limits.max_entry_size || total_size > self.limits.max_total_size {
+                return Err(
+                    "archive exceeds the extraction size limit, refusing to extract".to_string(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn run_rar_tool(&self, tool: &str, archive_path: &Path, staging: &Path) -> Result<()> {
+        let archive = archive_path.to_string_lossy().to_string();
+        let staging_str = staging.to_string_lossy().to_string();
+
+        let args: Vec<String> = match tool {
+            "unrar" => vec![
+                "x".into(),
+                "-o+".into(),
+                "-y".into(),
+                archive,
+                format!("{}{}", staging_str, std::path::MAIN_SEPARATOR),
+            ],
+            "bsdtar" => vec!["-xf".into(), archive, "-C".into(), staging_str],
+            _ => vec![
+                "x".into(),
+                "-y".into(),
+                format!("-o{}", staging_str),
+                archive,
+            ],
+        };
+
+        log::info!("Extracting {} with {}", archive_path.display(), tool);
+        run_tool(tool, &args, "extract", archive_path).await?;
+
+        Ok(())
+    }
+
+    /** Copies a directory tree into `destination` under the same limits as an archive
+     *
+     * `.git` folders are skipped, so a checkout imports only its working tree. The
+     * entry count and sizes are checked before anything is copied, since the tree may
+     * come from an external tool that applied no limits of its own.
+     */
+    pub fn import_directory(
+        &self,
+        source: &Path,
+        destination: &Path,
+    ) -> Result<Vec<ExtractedFile>> {
+        fn walk(
+            dir: &Path,
+            base: &Path,
+            limits: &ArchiveLimits,
+            files: &mut Vec<(String, PathBuf)>,
+            links: &mut Vec<(String, String)>,
+            total_size: &mut u64,
+        ) -> Result<()> {
+            for entry in std::fs::read_dir(dir)? {
+                let entry = entry?;
+                if entry.file_name() == ".git" {
+                    continue;
+                }
+                if files.len() + links.len() >= limits.max_entries {
+                    return Err(OpenCliError::Process(
+                        format!(
+                            "{} has more than {} entries, refusing to extract",
+                            base.display(),
+                            limits.max_entries
+                        )
+                        .into(),
+                    ));
+                }
+
+                let path = entry.path();
+                let metadata = std::fs::symlink_metadata(&path)?;
+                let file_type = metadata.file_type();
+                let relative = path
+                    .strip_prefix(base)
+                    .map(|p| p.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default();
+
+                if file_type.is_symlink() {
+                    let target = std::fs::read_link(&path)?;
+                    links.push((relative, target.to_string_lossy().to_string()));
+                } else if file_type.is_dir() {
+                    walk(&path, base, limits, files, links, total_size)?;
+                } else if file_type.is_file() {
+                    *total_size = total_size.saturating_add(metadata.len());
+                    if metadata.len() > limits.max_entry_size || *total_size > limits.max_total_size
+                    {
+                        return Err(OpenCliError::Process(
+                            format!(
+                                "{} exceeds the extraction size limit, refusing to extract",
+                                relative
+                            )
+                            .into(),
+                        ));
+                    }
+                    files.push((relative, path));
+                }
+            }
+            Ok(())
+        }
+
+        let mut files = Vec::new();
+        let mut links = Vec::new();
+        let mut total_size = 0;
+        walk(
+            source,
+            source,
+            &self.limits,
+            &mut files,
+            &mut links,
+            &mut total_size,
+        )?;
+        files.sort();
+
+        let mut state = ExtractionState::new(destination, self.limits)?;
+
+        for (name, path) in files {
+            let mut file = File::open(&path)?;
+            state.add_entry(&name, EntryKind::File, &mut file)?;
+        }
+
+        for (name, target) in links {
+            state.add_entry(
+                &name,
+                EntryKind::Link {
+                    target,
+                    hard: false,
+                },
+                &mut std::io::empty(),
+            )?;
+        }
+
+        Ok(state.files)
+    }
+
+    pub fn extract_tar<R: Read>(
+        &self,
+        reader: R,
+        destination: &Path,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut archive = tar::Archive::new(reader);
+        let mut state = ExtractionState::new(destination, self.limits)?;
+
+        for entry in archive.entries().map_err(|e| {
+            OpenCliError::Process(format!("Failed to read tar archive: {}", e).into())
+        })? {
+            let mut entry = entry.map_err(|e| {
+                OpenCliError::Process(format!("Failed to read tar entry: {}", e).into())
+            })?;
+
+            let name = entry
+                .path()
+                .map_err(|e| {
+                    OpenCliError::Process(format!("Invalid tar entry path: {}", e).into())
+                })?
+                .to_string_lossy()
+                .to_string();
+
+            let entry_type = entry.header().entry_type();
+
+            let kind = if entry_type.is_dir() {
+                EntryKind::Directory
+            } else if entry_type.is_file() {
+                EntryKind::File
+            } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+                let target = entry
+                    .link_name()
+                    .map_err(|e| {
+                        OpenCliError::Process(format!("Invalid tar link {}: {}", name, e).into())
+                    })?
+                    .map(|t| t.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                EntryKind::Link {
+                    target,
+                    hard: entry_type.is_hard_link(),
+                }
+            } else {
+                EntryKind::Other
+            };
+
+            state.add_entry(&name, kind, &mut entry)?;
+        }
+
+        Ok(state.files)
+    }
+}
+
+impl<'a> ExtractionState<'a> {
+    fn new(destination: &'a Path, limits: ArchiveLimits) -> Result<Self> {
+        std::fs::create_dir_all(destination)?;
+
+        Ok(Self {
+            destination,
+            limits,
+            entries: 0,
+            total_size: 0,
+            files: Vec::new(),
+            extracted: HashMap::new(),
+        })
+    }
+
+    fn add_entry(&mut self, name: &str, kind: EntryKind, reader: &mut dyn Read) -> Result<()> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            return Err(OpenCliError::Process(
+                format!(
+                    "Archive has more than {} entries, refusing to extract",
+                    self.limits.max_entries
+                )
+                .into(),
+            ));
+        }
+
+        let relative = sanitize_entry_path(name)?;
+        if relative.as_os_str().is_empty() {
+            return Ok(());
+        }
+        let output_path = self.destination.join(&relative);
+
+        match kind {
+            EntryKind::Directory => {
+                std::fs::create_dir_all(&output_path)?;
+            }
+            EntryKind::File => {
+                self.write_file(name, &relative, &output_path, reader)?;
+            }
+            EntryKind::Link { target, hard } => {
+                let target_relative =
+                    resolve_link_target(&relative, &target, hard).ok_or_else(|| {
+                        OpenCliError::Process(
+                            format!(
+                                "Archive link {} -> {} points outside the extraction directory",
+                                name, target
+                            )
+                            .into(),
+                        )
+                    })?;
+
+                // Links are materialized as copies so nothing on disk can point outside
+                match self.extracted.get(&target_relative).cloned() {
+                    Some(source) => {
+                        if let Some(parent) = output_path.parent() {
+                            std::fs::create_dir_all(parent)?;
+                        }
+                        let mut source_file = File::open(&source)?;
+                        self.write_file(name, &relative, &output_path, &mut source_file)?;
+                    }
+                    None => {
+                        log::warn!(
+                            "Skipping archive link {} -> {}: target not extracted",
+                            name,
+                            target
+                        );
+                    }
+                }
+            }
+            EntryKind::Other => {
+                log::warn!("Skipping unsupported archive entry: {}", name);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_file(
+        &mut self,
+        name: &str,
+        relative: &Path,
+        output_path: &Path,
+        reader: &mut dyn Read,
+    ) -> Result<()> {
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let remaining_total = self.limits.max_total_size.saturating_sub(self.total_size);
+        let limit = self.limits.max_entry_size.min(remaining_total);
+
+        let mut output = File::create(output_path)?;
+        let written = std::io::copy(&mut reader.take(limit + 1), &mut output).map_err(|e| {
+            OpenCliError::Process(format!("Failed to extract {}: {}", name, e).into())
+        })?;
+
+        if written > limit {
+            drop(output);
+            let _ = std::fs::remove_file(output_path);
+            return Err(OpenCliError::Process(
+                format!(
+                    "Archive entry {} exceeds the extraction size limit ({} bytes), refusing to extract",
+                    name, limit
+                )
+                .into(),
+            ));
+        }
+
+        self.total_size += written;
+        self.extracted
+            .insert(relative.to_path_buf(), output_path.to_path_buf());
+        self.files.push(ExtractedFile {
+            path: output_path.to_path_buf(),
+            archive_path: name.replace('\\', "/"),
+        });
+
+        Ok(())
+    }
+}
+
+/// First external tool on `PATH` that can read RAR archives
+fn rar_tool(archive_path: &Path) -> Result<&'static str> {
+    ["unrar", "7z", "7zz", "bsdtar"]
+        .into_iter()
+        .find(|tool| which::which(tool).is_ok())
+        .ok_or_else(|| {
+            OpenCliError::Process(
+                format!(
+                    "Cannot extract RAR archive {}: install 'unrar', '7z' or 'bsdtar' and retry",
+                    archive_path.display()
+                )
+                .into(),
+            )
+        })
+}
+
+async fn run_tool(tool: &str, args: &[String], what: &str, archive_path: &Path) -> Result<Vec<u8>> {
+    let output = tokio::process::Command::new(tool)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .map_err(|e| OpenCliError::Process(format!("Failed to run {}: {}", tool, e).into()))?;
+
+    if !output.status.success() {
+        return Err(OpenCliError::Process(
+            format!(
+                "{} failed to {} {}: {}",
+                tool,
+                what,
+                archive_path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into(),
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+/** Unpacked sizes of every entry in a listing from `unrar lt`, `7z l -slt` or `bsdtar -tvf`
+ *
+ * Folders count as entries of size 0.
+ */
+fn parse_rar_listing(tool: &str, listing: &str) -> Vec<u64> {
+    let number = |value: &str| value.trim().parse::<u64>().unwrap_or(0);
+
+    match tool {
+        "unrar" => {
+            let mut sizes = Vec::new();
+            for line in listing.lines().map(str::trim_start) {
+                if line.starts_with("Name:") {
+                    sizes.push(0);
+                } else if let (Some(value), Some(size)) =
+                    (line.strip_prefix("Size:"), sizes.last_mut())
+                {
+                    *size = number(value);
+                }
+            }
+            sizes
+        }
+        // One `ls -l` style line per entry, the size is the fifth column
+        "bsdtar" => listing
+            .lines()
+            .filter_map(|line| line.split_whitespace().nth(4))
+            .map(number)
+            .collect(),
+        // `7z` and `7zz`: the archive's own block comes before the `----------` line
+        _ => {
+            let mut sizes = Vec::new();
+            for line in listing
+                .lines()
+                .skip_while(|line| line.trim() != "----------")
+            {
+                if line.starts_with("Path = ") {
+                    sizes.push(0);
+                } else if let (Some(value), Some(size)) =
+                    (line.strip_prefix("Size = "), sizes.last_mut())
+                {
+                    *size = number(value);
+                }
+            }
+            sizes
+        }
+    }
+}
+
+fn sanitize_entry_path(name: &str) -> Result<PathBuf> {
+    let normalized = name.replace('\\', "/");
+    let unsafe_path = || {
+        OpenCliError::Process(
+            format!("Archive entry {} escapes the extraction directory", name).into(),
+        )
+    };
+
+    if normalized.starts_with('/')
+        || normalized
+            .split('/')
+            .next()
+            .is_some_and(|c| c.contains(':'))
+    {
+        return Err(unsafe_path());
+    }
+
+    let mut result = PathBuf::new();
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => result.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(unsafe_path());
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn resolve_link_target(entry: &Path, target: &str, hard: bool) -> Option<PathBuf> {
+    let target = target.replace('\\', "/");
+    if target.starts_with('/') || target.split('/').next().is_some_and(|c| c.contains(':')) {
+        return None;
+    }
+
+    // Hard link targets are relative to the archive root, symlinks to the entry's directory
+    let mut resolved: Vec<std::ffi::OsString> = if hard {
+        Vec::new()
+    } else {
+        entry
+            .parent()
+            .map(|p| p.iter().map(|c| c.to_os_string()).collect())
+            .unwrap_or_default()
+    };
+
+    for component in Path::new(&target).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part.to_os_string()),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop()?;
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    Some(resolved.iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "opencli-archive-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn sanitize_keeps_relative_paths() {
+        assert_eq!(
+            sanitize_entry_path("pkg/include/a.inc").unwrap(),
+            PathBuf::from("pkg/include/a.inc")
+        );
+        assert_eq!(
+            sanitize_entry_path("./pkg\\plugins\\a.dll").unwrap(),
+            PathBuf::from("pkg/plugins/a.dll")
+        );
+        assert_eq!(sanitize_entry_path("./").unwrap(), PathBuf::new());
+    }
+
+    #[test]
+    fn sanitize_rejects_parent_components() {
+        assert!(sanitize_entry_path("../evil.inc").is_err());
+        assert!(sanitize_entry_path("pkg/../../evil.inc").is_err());
+        assert!(sanitize_entry_path("pkg\\..\\..\\evil.inc").is_err());
+    }
+
+    #[test]
+    fn sanitize_rejects_absolute_paths() {
+        assert!(sanitize_entry_path("/etc/passwd").is_err());
+        assert!(sanitize_entry_path("\\Windows\\evil.dll").is_err());
+    }
+
+    #[test]
+    fn sanitize_rejects_drive_and_unc_prefixes() {
+        assert!(sanitize_entry_path("C:/Windows/evil.dll").is_err());
+        assert!(sanitize_entry_path("C:\\Windows\\evil.dll").is_err());
+        assert!(sanitize_entry_path("c:evil.dll").is_err());
+        assert!(sanitize_entry_path("\\\\server\\share\\evil.dll").is_err());
+        assert!(sanitize_entry_path("//server/share/evil.dll").is_err());
+    }
+
+    #[test]
+    fn symlink_targets_resolve_from_the_entry_directory() {
+        assert_eq!(
+            resolve_link_target(Path::new("pkg/lib/a.so"), "b.so", false),
+            Some(PathBuf::from("pkg/lib/b.so"))
+        );
+        assert_eq!(
+            resolve_link_target(Path::new("pkg/lib/a.so"), "../other/./b.so", false),
+            Some(PathBuf::from("pkg/other/b.so"))
+        );
+    }
+
+    #[test]
+    fn hard_link_targets_resolve_from_the_root() {
+        assert_eq!(
+            resolve_link_target(Path::new("pkg/lib/a.so"), "pkg/b.so", true),
+            Some(PathBuf::from("pkg/b.so"))
+        );
+        assert_eq!(
+            resolve_link_target(Path::new("pkg/lib/a.so"), "../b.so", true),
+            None
+        );
+    }
+
+    #[test]
+    fn link_targets_outside_the_root_are_rejected() {
+        assert_eq!(
+            resolve_link_target(Path::new("pkg/a.so"), "../../etc/passwd", false),
+            None
+        );
+        assert_eq!(
+            resolve_link_target(Path::new("a.so"), "../a.so", false),
+            None
+        );
+        assert_eq!(
+            resolve_link_target(Path::new("pkg/a.so"), "/etc/passwd", false),
+            None
+        );
+        assert_eq!(
+            resolve_link_target(Path::new("pkg/a.so"), "C:\\Windows\\a.dll", false),
+            None
+        );
+        assert_eq!(
+            resolve_link_target(Path::new("pkg/a.so"), "\\\\server\\share\\a.dll", false),
+            None
+        );
+    }
+
+    #[test]
+    fn link_chains_are_copied_from_their_final_target() {
+        let dir = scratch_dir("chain");
+        let mut state = ExtractionState::new(&dir, ArchiveLimits::default()).unwrap();
+
+        state
+            .add_entry("pkg/real.so", EntryKind::File, &mut &b"binary"[..])
+            .unwrap();
+        for (name, target) in [("pkg/first.so", "real.so"), ("pkg/second.so", "first.so")] {
+            state
+                .add_entry(
+                    name,
+                    EntryKind::Link {
+                        target: target.into(),
+                        hard: false,
+                    },
+                    &mut std::io::empty(),
+                )
+                .unwrap();
+        }
+
+        for name in ["real.so", "first.so", "second.so"] {
+            let path = dir.join("pkg").join(name);
+            assert!(!std::fs::symlink_metadata(&path)
+                .unwrap()
+                .file_type()
+                .is_symlink());
+            assert_eq!(std::fs::read(&path).unwrap(), b"binary");
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn imported_trees_are_checked_against_the_limits() {
+        let source = scratch_dir("import-source");
+        let destination = scratch_dir("import-destination");
+        std::fs::create_dir_all(source.join("pkg")).unwrap();
+        std::fs::write(source.join("pkg/a.inc"), b"12345").unwrap();
+        std::fs::write(source.join("pkg/b.inc"), b"12345").unwrap();
+
+        let too_few_entries = ArchiveExtractor::with_limits(ArchiveLimits {
+            max_entries: 1,
+            ..ArchiveLimits::default()
+        });
+        assert!(too_few_entries
+            .import_directory(&source, &destination)
+            .is_err());
+
+        let too_small = ArchiveExtractor::with_limits(ArchiveLimits {
+            max_total_size: 8,
+            ..ArchiveLimits::default()
+        });
+        assert!(too_small.import_directory(&source, &destination).is_err());
+        assert!(!destination.join("pkg").exists());
+
+        let files = ArchiveExtractor::new()
+            .import_directory(&source, &destination)
+            .unwrap();
+        assert_eq!(files.len(), 2);
+
+        std::fs::remove_dir_all(&source).unwrap();
+        std::fs::remove_dir_all(&destination).unwrap();
+    }
+
+    #[test]
+    fn escaping_links_fail_the_extraction() {
+        let dir = scratch_dir("escape");
+        let mut state = ExtractionState::new(&dir, ArchiveLimits::default()).unwrap();
+
+        state
+            .add_entry("pkg/a.so", EntryKind::File, &mut &b"binary"[..])
+            .unwrap();
+        let result = state.add_entry(
+            "pkg/link.so",
+            EntryKind::Link {
+                target: "../../outside.so".into(),
+                hard: false,
+            },
+            &mut std::io::empty(),
+        );
+
+        assert!(result.is_err());
+        assert!(!dir.join("pkg/link.so").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rar_listings_report_every_entry_size() {
+        let unrar = "\nArchive: pkg.rar\nDetails: RAR 5\n\n        Name: pkg/a.inc\n        Type: File\n        Size: 1200\n Packed size: 300\n\n        Name: pkg\n        Type: Directory\n";
+        assert_eq!(parse_rar_listing("unrar", unrar), vec![1200, 0]);
+
+        let seven = "Path = pkg.rar\nType = Rar5\nPhysical Size = 400\n\n----------\nPath = pkg/a.inc\nFolder = -\nSize = 1200\nPacked Size = 300\n\nPath = pkg\nFolder = +\nSize = 0\n";
+        assert_eq!(parse_rar_listing("7z", seven), vec![1200, 0]);
+
+        let bsdtar = "drwxr-xr-x  0 0      0           0 Oct 19 13:18 pkg/\n-rw-r--r--  0 0      0        1200 Oct 19 13:18 pkg/a b.inc\n";
+        assert_eq!(parse_rar_listing("bsdtar", bsdtar), vec![0, 1200]);
+    }
+
+    #[test]
+    fn rar_listings_are_checked_against_the_limits() {
+        let extractor = ArchiveExtractor::with_limits(ArchiveLimits {
+            max_entries: 3,
+            max_entry_size: 100,
+            max_total_size: 150,
+        });
+
+        assert!(extractor.check_listed_sizes(&[0, 100, 50]).is_ok());
+        assert!(extractor.check_listed_sizes(&[0, 0, 0, 0]).is_err());
+        assert!(extractor.check_listed_sizes(&[101]).is_err());
+        assert!(extractor.check_listed_sizes(&[100, 51]).is_err());
+    }
+}
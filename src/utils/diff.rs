@@ -0,0 +1,350 @@
+/// Errors are plain messages, callers add which patch and file they came from
+pub type Result<T> = std::result::Result<T, String>;
+
+/// Changes to one file from a unified diff
+#[derive(Debug, Clone)]
+pub struct FileDiff {
+    /// Path from the `+++` line, including any `b/` prefix
+    pub path: String,
+    pub hunks: Vec<Hunk>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    /// The `@@ -a,b +c,d @@` line, for error messages
+    pub header: String,
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(Vec<u8>),
+    Remove(Vec<u8>),
+    Add(Vec<u8>),
+    /// `\ No newline at end of file` after the previous line
+    NoNewline,
+}
+
+/** Parses a unified diff as written by `diff -u` or `git diff`
+ *
+ * Lines outside of file headers and hunks (`diff --git`, `index`, comments)
+ * are ignored. Creating or deleting files is not supported.
+ */
+pub fn parse(content: &[u8]) -> Result<Vec<FileDiff>> {
+    let lines: Vec<&[u8]> = content
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .collect();
+
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if line.starts_with(b"--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with(b"+++ ")) {
+            let old = header_path(line);
+            let new = header_path(lines[i + 1]);
+            if old == "/dev/null" || new == "/dev/null" {
+                return Err(format!(
+                    "creating or deleting files is not supported ({} -> {})",
+                    old, new
+                ));
+            }
+
+            files.push(FileDiff {
+                path: new,
+                hunks: Vec::new(),
+            });
+            i += 2;
+            continue;
+        }
+
+        if line.starts_with(b"@@ ") {
+            let file = files
+                .last_mut()
+                .ok_or_else(|| "hunk found before a ---/+++ file header".to_string())?;
+            let header = String::from_utf8_lossy(line).into_owned();
+            let (old_start, mut old_len, mut new_len) = parse_range(&header)?;
+
+            let mut hunk_lines = Vec::new();
+            i += 1;
+            while i < lines.len() && (old_len > 0 || new_len > 0) {
+                let line = lines[i];
+                match line.first() {
+                    Some(b' ') | None => {
+                        hunk_lines.push(HunkLine::Context(line.get(1..).unwrap_or(&[]).to_vec()));
+                        old_len = old_len.saturating_sub(1);
+                        new_len = new_len.saturating_sub(1);
+                    }
+                    Some(b'-') => {
+                        hunk_lines.push(HunkLine::Remove(line[1..].to_vec()));
+                        old_len = old_len.saturating_sub(1);
+                    }
+                    Some(b'+') => {
+                        hunk_lines.push(HunkLine::Add(line[1..].to_vec()));
+                        new_len = new_len.saturating_sub(1);
+                    }
+                    Some(b'\\') => hunk_lines.push(HunkLine::NoNewline),
+                    _ => return Err(format!("hunk {} ends early", header)),
+                }
+                i += 1;
+            }
+            if lines.get(i).is_some_and(|l| l.starts_with(b"\\")) {
+                hunk_lines.push(HunkLine::NoNewline);
+                i += 1;
+            }
+
+            file.hunks.push(Hunk {
+                header,
+                old_start,
+                lines: hunk_lines,
+            });
+            continue;
+        }
+
+        i += 1;
+    }
+
+    files.retain(|file| !file.hunks.is_empty());
+    if files.is_empty() {
+        return Err("no changes found, expected a unified diff".to_string());
+    }
+
+    Ok(files)
+}
+
+/** Applies the hunks of `diff` to `original`
+ *
+ * Hunks must match exactly but may have moved up or down, like `patch`
+ * without fuzz. Lines are compared without their `\r`, and added lines use
+ * the line ending of the original file.
+ */
+pub fn apply(original: &[u8], diff: &FileDiff) -> Result<Vec<u8>> {
+    let crlf = original.windows(2).any(|w| w == b"\r\n");
+    let mut trailing_newline = original.last() == Some(&b'\n');
+
+    let mut lines: Vec<Vec<u8>> = original
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line).to_vec())
+        .collect();
+    if trailing_newline || original.is_empty() {
+        lines.pop();
+    }
+
+    let mut offset: isize = 0;
+    let mut floor = 0;
+
+    for (number, hunk) in diff.hunks.iter().enumerate() {
+        let old: Vec<&[u8]> = hunk
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Remove(text) => Some(text.as_slice()),
+                _ => None,
+            })
+            .collect();
+        let new: Vec<Vec<u8>> = hunk
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Add(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+
+        // Hunks without old lines insert after `old_start`, the others start at it (1-based)
+        let start = if old.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = (start as isize + offset).max(0) as usize;
+        let position = find_block(&lines, &old, expected, floor).ok_or_else(|| {
+            format!(
+                "hunk {} ({}) does not match",
+                number + 1,
+                hunk.header.trim()
+            )
+        })?;
+
+        let end = position + old.len();
+        let touches_end = end == lines.len();
+        lines.splice(position..end, new.iter().cloned());
+        offset += new.len() as isize - old.len() as isize;
+        floor = position + new.len();
+
+        if touches_end {
+            if let Some(newline) = end_newline(&hunk.lines) {
+                trailing_newline = newline;
+            }
+        }
+    }
+
+    let eol: &[u8] = if crlf { b"\r\n" } else { b"\n" };
+    let mut patched = lines.join(eol);
+    if trailing_newline && !lines.is_empty() {
+        patched.extend_from_slice(eol);
+    }
+
+    Ok(patched)
+}
+
+fn header_path(line: &[u8]) -> String {
+    let path = String::from_utf8_lossy(&line[4..]).into_owned();
+    // `diff -u` puts a tab and a timestamp after the name
+    path.split('\t').next().unwrap_or("").trim().to_string()
+}
+
+fn parse_range(header: &str) -> Result<(usize, usize, usize)> {
+    let invalid = || format!("invalid hunk header {}", header);
+
+    let mut parts = header.split_whitespace().skip(1);
+    let old = parts
+        .next()
+        .and_then(|p| p.strip_prefix('-'))
+        .ok_or_else(invalid)?;
+    let new = parts
+        .next()
+        .and_then(|p| p.strip_prefix('+'))
+        .ok_or_else(invalid)?;
+
+    let range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_len) = range(old).ok_or_else(invalid)?;
+    let (_, new_len) = range(new).ok_or_else(invalid)?;
+
+    Ok((old_start, old_len, new_len))
+}
+
+/// Finds `block` at or after `floor`, trying `expected` first and then further away
+fn find_block(lines: &[Vec<u8>], block: &[&[u8]], expected: usize, floor: usize) -> Option<usize> {
+    let last = lines.len().checked_sub(block.len())?;
+    let matches = |at: usize| {
+        at >= floor
+            && at <= last
+            && lines[at..at + block.len()]
+                .iter()
+                .zip(block)
+                .all(|(line, wanted)| line.as_slice() == *wanted)
+    };
+
+    (0..=lines.len()).find_map(|distance| {
+        [
+            expected.checked_add(distance),
+            expected.checked_sub(distance),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|&at| matches(at))
+    })
+}
+
+/// Whether the new file ends with a newline, if the hunk says so with `\ No newline`
+fn end_newline(lines: &[HunkLine]) -> Option<bool> {
+    let mut old_missing = false;
+    let mut new_missing = false;
+
+    for (index, line) in lines.iter().enumerate() {
+        if *line != HunkLine::NoNewline || index == 0 {
+            continue;
+        }
+        match &lines[index - 1] {
+            HunkLine::Remove(_) => old_missing = true,
+            HunkLine::Add(_) => new_missing = true,
+            HunkLine::Context(_) => {
+                old_missing = true;
+                new_missing = true;
+            }
+            HunkLine::NoNewline => {}
+        }
+    }
+
+    (old_missing || new_missing).then_some(!new_missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patch(original: &str, diff: &str) -> Result<String> {
+        let files = parse(diff.as_bytes())?;
+        assert_eq!(files.len(), 1);
+        apply(original.as_bytes(), &files[0]).map(|out| String::from_utf8(out).unwrap())
+    }
+
+    const DIFF: &str =
+        "--- a/lib.inc\n+++ b/lib.inc\n@@ -2,3 +2,3 @@\n two\n-three\n+THREE\n four\n";
+
+    #[test]
+    fn applies_a_clean_hunk() {
+        let files = parse(DIFF.as_bytes()).unwrap();
+        assert_eq!(files[0].path, "b/lib.inc");
+        assert_eq!(files[0].hunks[0].old_start, 2);
+
+        assert_eq!(
+            patch("one\ntwo\nthree\nfour\nfive\n", DIFF).unwrap(),
+            "one\ntwo\nTHREE\nfour\nfive\n"
+        );
+    }
+
+    #[test]
+    fn applies_a_hunk_that_moved() {
+        assert_eq!(
+            patch("zero\nextra\none\ntwo\nthree\nfour\n", DIFF).unwrap(),
+            "zero\nextra\none\ntwo\nTHREE\nfour\n"
+        );
+        assert_eq!(
+            patch("two\nthree\nfour\n", DIFF).unwrap(),
+            "two\nTHREE\nfour\n"
+        );
+    }
+
+    #[test]
+    fn rejects_a_hunk_that_does_not_match() {
+        let error = patch("one\ntwo\n3\nfour\n", DIFF).unwrap_err();
+        assert!(error.contains("hunk 1"), "{}", error);
+
+        // A later hunk may not match above one that was already applied
+        let diff = "--- a/f\n+++ b/f\n@@ -3,1 +3,1 @@\n-b\n+B\n@@ -1,1 +1,1 @@\n-a\n+A\n";
+        assert!(patch("x\ny\nb\n", diff).unwrap_err().contains("hunk 2"));
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        assert_eq!(
+            patch("one\r\ntwo\r\nthree\r\nfour\r\n", DIFF).unwrap(),
+            "one\r\ntwo\r\nTHREE\r\nfour\r\n"
+        );
+
+        let crlf_diff = DIFF.replace('\n', "\r\n");
+        assert_eq!(
+            patch("two\r\nthree\r\nfour\r\n", &crlf_diff).unwrap(),
+            "two\r\nTHREE\r\nfour\r\n"
+        );
+    }
+
+    #[test]
+    fn follows_no_newline_at_end_of_file() {
+        let both = "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+c\n\\ No newline at end of file\n";
+        assert_eq!(patch("a\nb", both).unwrap(), "a\nc");
+
+        let adds = "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n";
+        assert_eq!(patch("a\nb", adds).unwrap(), "a\nb\n");
+
+        let removes =
+            "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\n-b\n+b\n\\ No newline at end of file\n";
+        assert_eq!(patch("a\nb\n", removes).unwrap(), "a\nb");
+
+        // Hunks away from the end leave a missing final newline alone
+        assert_eq!(
+            patch("one\ntwo\nthree\nfour\nfive", DIFF).unwrap(),
+            "one\ntwo\nTHREE\nfour\nfive"
+        );
+    }
+}
@@ -0,0 +1,579 @@
+use crate::result::{OpenCliError, Result};
+use crate::security::SecurityManager;
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::{Client, StatusCode};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const USER_AGENT: &str = "opencli/0.1.0";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_RETRIES: u32 = 4;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Forces offline mode for the rest of the process (`--offline`)
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// True when `--offline` was given or `OPENCLI_OFFLINE` is set to a truthy value
+pub fn is_offline() -> bool {
+    if OFFLINE.load(Ordering::Relaxed) {
+        return true;
+    }
+
+    std::env::var("OPENCLI_OFFLINE")
+        .map(|value| {
+            let value = value.trim().to_lowercase();
+            !value.is_empty() && value != "0" && value != "false" && value != "no"
+        })
+        .unwrap_or(false)
+}
+
+/// Fails fast with a clear message when `what` would need the network in offline mode
+pub fn ensure_online(what: &str) -> Result<()> {
+    if is_offline() {
+        return Err(OpenCliError::Process(
+            format!(
+                "Offline mode: cannot fetch {} (run without --offline or unset OPENCLI_OFFLINE)",
+                what
+            )
+            .into(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOptions {
+    pub expected_sha256: Option<String>,
+    /// Report into an existing bar (e.g. one row of a MultiProgress) instead of creating one
+    pub progress_bar: Option<ProgressBar>,
+    /// Extra request headers, e.g. a token for a private GitLab or Gitea server
+    pub headers: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Clone)]
+pub struct DownloadManager {
+    client: Client,
+    max_retries: u32,
+}
+
+enum AttemptError {
+    Retry(OpenCliError),
+    Fatal(OpenCliError),
+}
+
+impl Default for DownloadManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DownloadManager {
+    pub fn new() -> Self {
+        let client = Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .read_timeout(READ_TIMEOUT)
+            .build()
+            .unwrap_or_else(|_| Client::new());
+
+        Self {
+            client,
+            max_retries: MAX_RETRIES,
+        }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub async fn download(
+        &self,
+        url: &str,
+        destination: &Path,
+        label: &str,
+        options: &DownloadOptions,
+    ) -> Result<DownloadedFile> {
+        ensure_online(label)?;
+
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let part_path = part_path(destination);
+        let owned_bar = options.progress_bar.is_none();
+        let pb = match &options.progress_bar {
+            Some(pb) => {
+                pb.reset();
+                pb.set_style(Self::bar_style());
+                pb.set_message(label.to_string());
+                pb.clone()
+            }
+            None => self.create_progress_bar(label),
+        };
+        let fail = |message: String| {
+            if owned_bar {
+                pb.abandon_with_message(message);
+            } else {
+                pb.set_message(message);
+            }
+        };
+
+        let mut attempt = 0;
+        let (size, sha256) = loop {
+            match self
+                .try_download(url, &part_path, &options.headers, &pb)
+                .await
+            {
+                Ok(result) => break result,
+                Err(AttemptError::Fatal(e)) => {
+                    fail(format!("Failed {}", label));
+                    return Err(e);
+                }
+                Err(AttemptError::Retry(e)) if attempt < self.max_retries => {
+                    let delay = backoff(attempt);
+                    attempt += 1;
+                    log::warn!(
+                        "Download of {} failed ({}), retry {}/{} in {:?}",
+                        label,
+                        e,
+                        attempt,
+                        self.max_retries,
+                        delay
+                    );
+                    pb.set_message(format!(
+                        "Retrying {} ({}/{})",
+                        label, attempt, self.max_retries
+                    ));
+                    tokio::time::sleep(delay).await;
+                }
+                Err(AttemptError::Retry(e)) => {
+                    fail(format!("Failed {}", label));
+                    return Err(OpenCliError::Process(
+                        format!(
+                            "{} for {} after {} attempts: {}",
+                            OpenCliError::DOWNLOAD_FAILED,
+                            label,
+                            attempt + 1,
+                            e
+                        )
+                        .into(),
+                    ));
+                }
+            }
+        };
+
+        if let Some(expected) = &options.expected_sha256 {
+            if !SecurityManager::digest_matches(expected, &sha256) {
+                discard_partial(&part_path).await?;
+                fail(format!("Checksum mismatch for {}", label));
+                return Err(OpenCliError::Process(
+                    format!(
+                        "Checksum mismatch for {}: expected sha256 {}, got sha256 {}",
+                        label, expected, sha256
+                    )
+                    .into(),
+                ));
+            }
+        }
+
+        fs::rename(&part_path, destination).await?;
+        discard_partial(&part_path).await?;
+        if owned_bar {
+            pb.finish_with_message(format!("Downloaded {}", label));
+        } else {
+            pb.set_message(format!("Downloaded {}", label));
+        }
+        log::info!("Downloaded {} ({} bytes, sha256 {})", url, size, sha256);
+
+        Ok(DownloadedFile {
+            path: destination.to_path_buf(),
+            size,
+            sha256,
+        })
+    }
+
+    pub async fn fetch_text(&self, url: &str, what: &str) -> Result<String> {
+        self.fetch_text_inner(url, what, false, &[])
+            .await?
+            .ok_or_else(|| OpenCliError::NotFound(format!("{} not found", what).into()))
+    }
+
+    /// Like `fetch_text`, but a 404 is `Ok(None)` instead of an error
+    pub async fn fetch_optional_text(&self, url: &str, what: &str) -> Result<Option<String>> {
+        self.fetch_text_inner(url, what, true, &[]).await
+    }
+
+    /// Fetches and parses a JSON API response, sending `headers` with the request
+    pub async fn fetch_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        what: &str,
+        headers: &[(String, String)],
+    ) -> Result<T> {
+        let text = self
+            .fetch_text_inner(url, what, false, headers)
+            .await?
+            .ok_or_else(|| OpenCliError::NotFound(format!("{} not found", what).into()))?;
+
+        serde_json::from_str(&text).map_err(|e| {
+            OpenCliError::Process(format!("Invalid response for {}: {}", what, e).into())
+        })
+    }
+
+    async fn fetch_text_inner(
+        &self,
+        url: &str,
+        what: &str,
+        allow_missing: bool,
+        headers: &[(String, String)],
+    ) -> Result<Option<String>> {
+        ensure_online(what)?;
+
+        let mut attempt = 0;
+
+        loop {
+            let mut request = self.client.get(url);
+            for (name, value) in headers {
+                request = request.header(name.as_str(), value.as_str());
+            }
+
+            let result = match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    response.text().await.map(Some).map_err(|e| {
+                        AttemptError::Retry(OpenCliError::Process(
+                            format!("Failed to read {}: {}", what, e).into(),
+                        ))
+                    })
+                }
+                Ok(response) if allow_missing && response.status() == StatusCode::NOT_FOUND => {
+                    Ok(None)
+                }
+                Ok(response) => Err(status_error(response.status(), what)),
+                Err(e) => Err(AttemptError::Retry(OpenCliError::Process(
+                    format!("Failed to download {}: {}", what, e).into(),
+                ))),
+            };
+
+            match result {
+                Ok(text) => return Ok(text),
+                Err(AttemptError::Retry(_)) if attempt < self.max_retries => {
+                    tokio::time::sleep(backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Err(AttemptError::Retry(e)) | Err(AttemptError::Fatal(e)) => return Err(e),
+            }
+        }
+    }
+
+    async fn try_download(
+        &self,
+        url: &str,
+        part_path: &Path,
+        headers: &[(String, String)],
+        pb: &ProgressBar,
+    ) -> std::result::Result<(u64, String), AttemptError> {
+        let validator_path = validator_path(part_path);
+        let mut existing = match fs::metadata(part_path).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+        let validator = fs::read_to_string(&validator_path).await.ok();
+        if existing > 0 && validator.is_none() {
+            // Without an ETag or Last-Modified the partial file cannot be proven current
+            log::info!("Discarding partial download of {} without a validator", url);
+            discard_partial(part_path)
+                .await
+                .map_err(AttemptError::Fatal)?;
+            existing = 0;
+        }
+
+        let (mut response, resumed) = loop {
+            let mut request = self.client.get(url);
+            for (name, value) in headers {
+                request = request.header(name.as_str(), value.as_str());
+            }
+            if let (true, Some(validator)) = (existing > 0, &validator) {
+                request = request
+                    .header(reqwest::header::RANGE, format!("bytes={}-", existing))
+                    .header(reqwest::header::IF_RANGE, validator.trim());
+            }
+
+            let response = request.send().await.map_err(|e| {
+                AttemptError::Retry(OpenCliError::Process(
+                    format!("Request failed: {}", e).into(),
+                ))
+            })?;
+
+            let content_range = response
+                .headers()
+                .get(reqwest::header::CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_content_range);
+
+            match response.status() {
+                StatusCode::PARTIAL_CONTENT if existing > 0 => {
+                    if content_range.is_some_and(|range| range.start == Some(existing)) {
+                        break (response, true);
+                    }
+                    log::warn!(
+                        "Server resumed {} at another offset than byte {}, restarting",
+                        url,
+                        existing
+                    );
+                }
+                StatusCode::RANGE_NOT_SATISFIABLE if existing > 0 => {
+                    if content_range.is_some_and(|range| range.total == Some(existing)) {
+                        // The partial file already holds the whole body
+                        let sha256 = hash_existing(part_path)
+                            .await
+                            .map_err(AttemptError::Fatal)?;
+                        pb.set_length(existing);
+                        pb.set_position(existing);
+                        return Ok((existing, sha256));
+                    }
+                    log::warn!(
+                        "Partial download of {} does not match the remote file, restarting",
+                        url
+                    );
+                }
+                StatusCode::PARTIAL_CONTENT => {
+                    return Err(AttemptError::Retry(OpenCliError::Process(
+                        format!("Unexpected partial response for {}", url).into(),
+                    )))
+                }
+                s if s.is_success() => break (response, false),
+                s => return Err(status_error(s, url)),
+            }
+
+            discard_partial(part_path)
+                .await
+                .map_err(AttemptError::Fatal)?;
+            existing = 0;
+        };
+
+        if !resumed {
+            // Remember what this body was so a later resume can ask for the same one
+            let validator = response
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|value| value.to_str().ok())
+                .filter(|etag| !etag.starts_with("W/"))
+                .or_else(|| {
+                    response
+                        .headers()
+                        .get(reqwest::header::LAST_MODIFIED)
+                        .and_then(|value| value.to_str().ok())
+                });
+            match validator {
+                Some(validator) => fs::write(&validator_path, validator).await,
+                None => fs::remove_file(&validator_path).await.or(Ok(())),
+            }
+            .map_err(|e| AttemptError::Fatal(e.into()))?;
+        }
+
+        let mut hasher = Sha256::new();
+        let mut written = 0;
+
+        let mut file = if resumed {
+            hash_into(part_path, &mut hasher)
+                .await
+                .map_err(AttemptError::Fatal)?;
+            written = existing;
+            log::info!("Resuming download of {} at byte {}", url, existing);
+            OpenOptions::new()
+                .append(true)
+                .open(part_path)
+                .await
+                .map_err(|e| AttemptError::Fatal(e.into()))?
+        } else {
+            File::create(part_path)
+                .await
+                .map_err(|e| AttemptError::Fatal(e.into()))?
+        };
+
+        if let Some(remaining) = response.content_length() {
+            pb.set_length(written + remaining);
+        }
+        pb.set_position(written);
+
+        loop {
+            let chunk = response.chunk().await.map_err(|e| {
+                AttemptError::Retry(OpenCliError::Process(
+                    format!("Connection interrupted: {}", e).into(),
+                ))
+            })?;
+
+            let Some(chunk) = chunk else {
+                break;
+            };
+
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| AttemptError::Fatal(e.into()))?;
+            hasher.update(&chunk);
+            written += chunk.len() as u64;
+            pb.set_position(written);
+        }
+
+        file.flush()
+            .await
+            .map_err(|e| AttemptError::Fatal(e.into()))?;
+
+        Ok((written, format!("{:x}", hasher.finalize())))
+    }
+
+    fn create_progress_bar(&self, label: &str) -> ProgressBar {
+        let pb = ProgressBar::new(0);
+        pb.set_style(Self::bar_style());
+        pb.set_message(label.to_string());
+        pb
+    }
+
+    fn bar_style() -> ProgressStyle {
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta}) {msg}")
+            .unwrap()
+            .progress_chars("#>-")
+    }
+}
+
+pub fn part_path(destination: &Path) -> PathBuf {
+    let mut name = destination
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(".part");
+    destination.with_file_name(name)
+}
+
+/// ETag or Last-Modified of the body a `.part` file holds, sent as `If-Range` on resume
+fn validator_path(part_path: &Path) -> PathBuf {
+    let mut path = part_path.as_os_str().to_os_string();
+    path.push(".validator");
+    PathBuf::from(path)
+}
+
+/// Removes a partial download and its validator, whichever exist
+async fn discard_partial(part_path: &Path) -> Result<()> {
+    for path in [part_path.to_path_buf(), validator_path(part_path)] {
+        match fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ContentRange {
+    start: Option<u64>,
+    total: Option<u64>,
+}
+
+/// Parses `bytes 100-199/200`, `bytes 100-199/*` or `bytes */200`
+fn parse_content_range(value: &str) -> Option<ContentRange> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let total = match total {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    let start = match range {
+        "*" => None,
+        range => Some(range.split_once('-')?.0.parse().ok()?),
+    };
+
+    Some(ContentRange { start, total })
+}
+
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF)
+}
+
+fn status_error(status: StatusCode, what: &str) -> AttemptError {
+    let error =
+        OpenCliError::Process(format!("Failed to download {}: HTTP {}", what, status).into());
+
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        AttemptError::Retry(error)
+    } else {
+        AttemptError::Fatal(error)
+    }
+}
+
+async fn hash_into(path: &Path, hasher: &mut Sha256) -> Result<()> {
+    let mut file = File::open(path).await?;
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(())
+}
+
+async fn hash_existing(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hash_into(path, &mut hasher).await?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_range_reports_start_and_total() {
+        assert_eq!(
+            parse_content_range("bytes 100-199/200"),
+            Some(ContentRange {
+                start: Some(100),
+                total: Some(200)
+            })
+        );
+        assert_eq!(
+            parse_content_range("bytes 100-199/*"),
+            Some(ContentRange {
+                start: Some(100),
+                total: None
+            })
+        );
+        assert_eq!(
+            parse_content_range("bytes */200"),
+            Some(ContentRange {
+                start: None,
+                total: Some(200)
+            })
+        );
+    }
+
+    #[test]
+    fn malformed_content_range_is_rejected() {
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+        assert_eq!(parse_content_range("bytes 100/200"), None);
+        assert_eq!(parse_content_range("bytes x-199/200"), None);
+    }
+}
@@ -1 +1,5 @@
+pub mod archive;
+pub mod diff;
+pub mod download;
+pub mod platform;
 pub mod process;
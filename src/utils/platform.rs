@@ -0,0 +1,217 @@
+use crate::result::{OpenCliError, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+static WINDOWS_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(^|[^a-z])(win(dows)?(32|64)?|msvc|mingw[a-z0-9_]*)([^a-z]|$)|\.(dll|exe)$")
+        .unwrap()
+});
+static LINUX_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(^|[^a-z])(linux|ubuntu|debian)([^a-z]|$)|\.so$").unwrap());
+static MACOS_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(^|[^a-z])(mac(os)?|osx|darwin|apple)([^a-z]|$)|\.dylib$").unwrap());
+
+/// Operating system a server or its binaries are built for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Windows,
+    Linux,
+    #[serde(alias = "darwin")]
+    Macos,
+}
+
+impl Platform {
+    pub const ALL: [Platform; 3] = [Platform::Windows, Platform::Linux, Platform::Macos];
+
+    /// The platform opencli runs on, `None` on systems open.mp does not ship for
+    pub fn current() -> Option<Platform> {
+        if cfg!(target_os = "windows") {
+            Some(Platform::Windows)
+        } else if cfg!(target_os = "linux") {
+            Some(Platform::Linux)
+        } else if cfg!(target_os = "macos") {
+            Some(Platform::Macos)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Windows => "windows",
+            Platform::Linux => "linux",
+            Platform::Macos => "macos",
+        }
+    }
+
+    /** The platform an asset or binary is built for, judged by its name
+     *
+     * Names like `plugin-linux.tar.gz`, `x-win32.zip` or `foo.dll` belong to one
+     * platform; names that mention none (or several) belong to all of them.
+     */
+    pub fn of_name(name: &str) -> Option<Platform> {
+        // The regex crate is built without Unicode case folding, so compare lowercase
+        let name = name.to_ascii_lowercase();
+        let mut found = Platform::ALL
+            .into_iter()
+            .filter(|platform| platform.regex().is_match(&name));
+
+        match (found.next(), found.next()) {
+            (Some(platform), None) => Some(platform),
+            _ => None,
+        }
+    }
+
+    /// Whether `name` is usable on any of `platforms`; an empty list accepts everything
+    pub fn matches_any(name: &str, platforms: &[Platform]) -> bool {
+        platforms.is_empty()
+            || Platform::of_name(name).is_none_or(|platform| platforms.contains(&platform))
+    }
+
+    /// Whether a binary file is usable on any of `platforms`, judged by its file name
+    pub fn accepts_file(path: &Path, platforms: &[Platform]) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_none_or(|name| Platform::matches_any(name, platforms))
+    }
+
+    fn regex(&self) -> &'static Regex {
+        match self {
+            Platform::Windows => &WINDOWS_REGEX,
+            Platform::Linux => &LINUX_REGEX,
+            Platform::Macos => &MACOS_REGEX,
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Platform {
+    type Err = OpenCliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "windows" | "win" | "win32" | "win64" => Ok(Platform::Windows),
+            "linux" => Ok(Platform::Linux),
+            "macos" | "darwin" | "osx" | "mac" => Ok(Platform::Macos),
+            other => Err(OpenCliError::Config(
+                format!(
+                    "Unknown platform \"{}\", expected windows, linux or macos",
+                    other
+                )
+                .into(),
+            )),
+        }
+    }
+}
+
+/// Parses a comma separated `--platform` value such as `windows,linux`
+pub fn parse_platforms(value: &str) -> Result<Vec<Platform>> {
+    let mut platforms = value
+        .split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(Platform::from_str)
+        .collect::<Result<Vec<_>>>()?;
+    platforms.sort();
+    platforms.dedup();
+
+    Ok(platforms)
+}
+
+/// `windows, linux` for messages, `every platform` for an empty list
+pub fn platform_list(platforms: &[Platform]) -> String {
+    if platforms.is_empty() {
+        return "every platform".to_string();
+    }
+
+    platforms
+        .iter()
+        .map(Platform::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asset_names_map_to_their_platform() {
+        let cases = [
+            ("crashdetect-4.22-win32.zip", Some(Platform::Windows)),
+            ("mysql-R41-4-win32.zip", Some(Platform::Windows)),
+            ("Plugin-Windows-x64.zip", Some(Platform::Windows)),
+            ("streamer-mingw64.7z", Some(Platform::Windows)),
+            ("pawnraknet.dll", Some(Platform::Windows)),
+            ("omp-server.exe", Some(Platform::Windows)),
+            ("sscanf-2.13.8-linux.tar.gz", Some(Platform::Linux)),
+            ("crashdetect-4.22-linux.tar.gz", Some(Platform::Linux)),
+            ("plugin-ubuntu-22.04.tar.gz", Some(Platform::Linux)),
+            ("pawnraknet.so", Some(Platform::Linux)),
+            ("plugin-macos-arm64.zip", Some(Platform::Macos)),
+            ("plugin-darwin.tar.gz", Some(Platform::Macos)),
+            ("plugin-mac.zip", Some(Platform::Macos)),
+            ("plugin-apple-silicon.zip", Some(Platform::Macos)),
+            ("libplugin.dylib", Some(Platform::Macos)),
+        ];
+
+        for (name, expected) in cases {
+            assert_eq!(Platform::of_name(name), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn platform_words_only_match_on_word_boundaries() {
+        let cases = [
+            "twinkle-1.0.zip",
+            "winter.inc",
+            "machine-1.0.zip",
+            "pineapple-2.0.tar.gz",
+            "YSI-Includes-5.10.zip",
+            "source.tar.gz",
+        ];
+
+        for name in cases {
+            assert_eq!(Platform::of_name(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn names_of_several_platforms_belong_to_all() {
+        let cases = [
+            "sscanf-2.13.8-win32-linux.zip",
+            "plugin-1.0-windows-macos.zip",
+            "plugin-linux.dll",
+        ];
+
+        for name in cases {
+            assert_eq!(Platform::of_name(name), None, "{}", name);
+            for platform in Platform::ALL {
+                assert!(Platform::matches_any(name, &[platform]), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn platform_filters_keep_matching_and_generic_names() {
+        let linux = [Platform::Linux];
+
+        assert!(Platform::matches_any("sscanf-2.13.8-linux.tar.gz", &linux));
+        assert!(Platform::matches_any("YSI-Includes-5.10.zip", &linux));
+        assert!(!Platform::matches_any("crashdetect-4.22-win32.zip", &linux));
+        assert!(Platform::matches_any("crashdetect-4.22-win32.zip", &[]));
+        assert!(!Platform::accepts_file(
+            Path::new("plugins/pawnraknet.dll"),
+            &linux
+        ));
+    }
+}